@@ -0,0 +1,542 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr};
+
+mod keys;
+
+use keys::{button_from_name, button_label, key_from_name, key_label};
+
+/// Overrides for the default bindings, read once at startup. Every action listed
+/// replaces all of its defaults, e.g.
+/// `(bindings: { ToggleRun: ["KeyP"], RotateCamera: ["Alt+MouseRight"] })`.
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// Everything the keyboard and mouse buttons can do, independent of which key does it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    SelectSlot(u8),
+    PickBlock,
+    ResetCamera,
+    RotateCamera,
+    PanCamera,
+    ToggleRun,
+    ToggleEdition,
+    ToggleQuasiConnectivity,
+    ToggleCompiled,
+    TogglePowerLabels,
+    ToggleSignalFlow,
+    ToggleTrace,
+    ScrollTraceUp,
+    ScrollTraceDown,
+    ExportTrace,
+    Faster,
+    Slower,
+    Undo,
+    Redo,
+    Rewind,
+    Step,
+    Save,
+    PreviousSave,
+    NextSave,
+    Load,
+    Export,
+    OpenConsole,
+    SelectMode,
+    Copy,
+    Cut,
+    Paste,
+    RotateClipboard,
+    MirrorClipboard,
+    RotatePlacement,
+    RotateBlock,
+    InspectBlock,
+    PreviousField,
+    NextField,
+    DecreaseField,
+    IncreaseField,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// A key or mouse button with the modifiers that have to be held. Ctrl and Alt must
+/// match exactly, so `KeyC` doesn't fire on Ctrl+C. Shift is only checked when
+/// required, some actions go faster while it is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    input: Input,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Binding {
+    const fn key(key: KeyCode) -> Self {
+        Self {
+            input: Input::Key(key),
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    const fn mouse(button: MouseButton) -> Self {
+        Self {
+            input: Input::Mouse(button),
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    const fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    const fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    fn modifiers_held(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        ctrl == self.ctrl && alt == self.alt && (shift || !self.shift)
+    }
+
+    fn check(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+        key_state: impl Fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+        button_state: impl Fn(&ButtonInput<MouseButton>, MouseButton) -> bool,
+    ) -> bool {
+        let input = match self.input {
+            Input::Key(key) => key_state(keys, key),
+            Input::Mouse(button) => button_state(mouse, button),
+        };
+        input && self.modifiers_held(keys)
+    }
+
+    fn modifiers(&self) -> impl Iterator<Item = &'static str> {
+        [
+            (self.ctrl, "Ctrl"),
+            (self.alt, "Alt"),
+            (self.shift, "Shift"),
+        ]
+        .into_iter()
+        .filter(|(held, _)| *held)
+        .map(|(_, name)| name)
+    }
+
+    /// Short form for the help text, e.g. `Ctrl+Z`.
+    fn label(&self) -> String {
+        let input = match self.input {
+            Input::Key(key) => key_label(key),
+            Input::Mouse(button) => button_label(button),
+        };
+        let mut parts: Vec<&str> = self.modifiers().collect();
+        parts.push(&input);
+        parts.join("+")
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    /// Modifiers first, then the key or button, e.g. `Ctrl+KeyZ` or `Alt+MouseRight`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let Some(name) = parts.pop() else {
+            return Err("empty binding".to_string());
+        };
+
+        let mut binding = if let Some(key) = key_from_name(name) {
+            Binding::key(key)
+        } else if let Some(button) = button_from_name(name) {
+            Binding::mouse(button)
+        } else {
+            return Err(format!("unknown key {}", name));
+        };
+
+        for modifier in parts {
+            match modifier {
+                "Ctrl" => binding.ctrl = true,
+                "Alt" => binding.alt = true,
+                "Shift" => binding.shift = true,
+                modifier => return Err(format!("unknown modifier {}", modifier)),
+            }
+        }
+        Ok(binding)
+    }
+}
+
+const DEFAULT_BINDINGS: &[(Action, Binding)] = &[
+    (Action::SelectSlot(0), Binding::key(KeyCode::Digit1)),
+    (Action::SelectSlot(1), Binding::key(KeyCode::Digit2)),
+    (Action::SelectSlot(2), Binding::key(KeyCode::Digit3)),
+    (Action::SelectSlot(3), Binding::key(KeyCode::Digit4)),
+    (Action::SelectSlot(4), Binding::key(KeyCode::Digit5)),
+    (Action::SelectSlot(5), Binding::key(KeyCode::Digit6)),
+    (Action::SelectSlot(6), Binding::key(KeyCode::Digit7)),
+    (Action::SelectSlot(7), Binding::key(KeyCode::Digit8)),
+    (Action::SelectSlot(8), Binding::key(KeyCode::Digit9)),
+    (Action::PickBlock, Binding::mouse(MouseButton::Middle)),
+    (Action::ResetCamera, Binding::key(KeyCode::Space)),
+    (
+        Action::RotateCamera,
+        Binding::mouse(MouseButton::Right).with_ctrl(),
+    ),
+    (
+        Action::PanCamera,
+        Binding::mouse(MouseButton::Middle).with_ctrl(),
+    ),
+    (Action::ToggleRun, Binding::key(KeyCode::Tab)),
+    (Action::ToggleEdition, Binding::key(KeyCode::KeyE)),
+    (Action::ToggleQuasiConnectivity, Binding::key(KeyCode::KeyQ)),
+    (Action::ToggleCompiled, Binding::key(KeyCode::KeyC)),
+    (Action::TogglePowerLabels, Binding::key(KeyCode::KeyO)),
+    (Action::ToggleSignalFlow, Binding::key(KeyCode::KeyF)),
+    (Action::ToggleTrace, Binding::key(KeyCode::KeyT)),
+    (Action::ScrollTraceUp, Binding::key(KeyCode::PageUp)),
+    (Action::ScrollTraceDown, Binding::key(KeyCode::PageDown)),
+    (Action::ExportTrace, Binding::key(KeyCode::KeyT).with_ctrl()),
+    (Action::Faster, Binding::key(KeyCode::Equal)),
+    (Action::Slower, Binding::key(KeyCode::Minus)),
+    (Action::Undo, Binding::key(KeyCode::KeyZ).with_ctrl()),
+    (Action::Redo, Binding::key(KeyCode::KeyY).with_ctrl()),
+    (Action::Rewind, Binding::key(KeyCode::BracketLeft)),
+    (Action::Step, Binding::key(KeyCode::BracketRight)),
+    (Action::Save, Binding::key(KeyCode::F5)),
+    (Action::PreviousSave, Binding::key(KeyCode::F6)),
+    (Action::NextSave, Binding::key(KeyCode::F7)),
+    (Action::Load, Binding::key(KeyCode::F9)),
+    (Action::Export, Binding::key(KeyCode::F8)),
+    (Action::OpenConsole, Binding::key(KeyCode::Backquote)),
+    (Action::OpenConsole, Binding::key(KeyCode::Slash)),
+    (Action::SelectMode, Binding::key(KeyCode::KeyB)),
+    (Action::Copy, Binding::key(KeyCode::KeyC).with_ctrl()),
+    (Action::Cut, Binding::key(KeyCode::KeyX).with_ctrl()),
+    (Action::Paste, Binding::key(KeyCode::KeyV).with_ctrl()),
+    (
+        Action::RotateClipboard,
+        Binding::key(KeyCode::KeyR).with_ctrl(),
+    ),
+    (
+        Action::MirrorClipboard,
+        Binding::key(KeyCode::KeyM).with_ctrl(),
+    ),
+    (Action::RotatePlacement, Binding::key(KeyCode::KeyR)),
+    (Action::RotateBlock, Binding::key(KeyCode::KeyR).with_alt()),
+    (Action::InspectBlock, Binding::key(KeyCode::KeyI)),
+    (Action::PreviousField, Binding::key(KeyCode::ArrowUp)),
+    (Action::NextField, Binding::key(KeyCode::ArrowDown)),
+    (Action::DecreaseField, Binding::key(KeyCode::ArrowLeft)),
+    (Action::IncreaseField, Binding::key(KeyCode::ArrowRight)),
+];
+
+enum Help {
+    Actions(&'static [Action], &'static str),
+    Fixed(&'static str),
+}
+
+/// Order of the help line, the hotbar comes first.
+const HELP: &[Help] = &[
+    Help::Actions(&[Action::PickBlock], "Pick Block"),
+    Help::Fixed("(Ctrl+Wheel) Zoom"),
+    Help::Actions(&[Action::ResetCamera], "Center Camera"),
+    Help::Actions(&[Action::RotateCamera], "Rotate"),
+    Help::Actions(&[Action::PanCamera], "Pan"),
+    Help::Actions(&[Action::ToggleRun], "Run/Pause"),
+    Help::Actions(&[Action::ToggleEdition], "Java/Bedrock"),
+    Help::Actions(&[Action::ToggleQuasiConnectivity], "QC"),
+    Help::Actions(&[Action::ToggleCompiled], "Compiled"),
+    Help::Actions(&[Action::TogglePowerLabels], "Power Labels"),
+    Help::Actions(&[Action::ToggleSignalFlow], "Signal Flow"),
+    Help::Actions(&[Action::ToggleTrace], "Trace"),
+    Help::Actions(
+        &[Action::ScrollTraceUp, Action::ScrollTraceDown],
+        "Scroll Trace",
+    ),
+    Help::Actions(&[Action::ExportTrace], "Export Trace"),
+    Help::Actions(&[Action::Slower, Action::Faster], "Speed"),
+    Help::Actions(&[Action::Undo, Action::Redo], "Undo/Redo"),
+    Help::Actions(&[Action::Rewind, Action::Step], "Rewind/Step"),
+    Help::Actions(&[Action::Save], "Save"),
+    Help::Actions(&[Action::PreviousSave, Action::NextSave], "Pick"),
+    Help::Actions(&[Action::Load], "Load"),
+    Help::Fixed("(Drop .schem/.litematic) Import"),
+    Help::Actions(&[Action::Export], "Export"),
+    Help::Actions(&[Action::OpenConsole], "Console"),
+    Help::Actions(&[Action::SelectMode], "Select"),
+    Help::Actions(
+        &[Action::Copy, Action::Cut, Action::Paste],
+        "Copy/Cut/Paste",
+    ),
+    Help::Actions(
+        &[Action::RotateClipboard, Action::MirrorClipboard],
+        "Rotate/Mirror",
+    ),
+    Help::Fixed("(Drag/Shift+Drag) Line/Plane"),
+    Help::Actions(&[Action::RotatePlacement], "Turn"),
+    Help::Actions(&[Action::RotateBlock], "Turn Placed"),
+    Help::Actions(&[Action::InspectBlock], "Pin Inspector"),
+    Help::Actions(
+        &[
+            Action::PreviousField,
+            Action::NextField,
+            Action::DecreaseField,
+            Action::IncreaseField,
+        ],
+        "Edit",
+    ),
+];
+
+#[derive(Deserialize, Default)]
+struct Settings {
+    #[serde(default)]
+    bindings: HashMap<Action, Vec<String>>,
+}
+
+#[derive(Resource)]
+pub struct KeyBindings {
+    actions: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut actions: HashMap<Action, Vec<Binding>> = HashMap::new();
+        for (action, binding) in DEFAULT_BINDINGS {
+            actions.entry(*action).or_default().push(*binding);
+        }
+        Self { actions }
+    }
+}
+
+impl KeyBindings {
+    /// The defaults with every action listed in the settings file rebound, a missing
+    /// file keeps the defaults.
+    fn load(path: &Path) -> Self {
+        let mut bindings = Self::default();
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return bindings,
+            Err(err) => {
+                warn!("Could not read {}: {}", path.display(), err);
+                return bindings;
+            }
+        };
+        let settings: Settings = match ron::from_str(&text) {
+            Ok(settings) => settings,
+            Err(err) => {
+                warn!("Malformed settings file {}: {}", path.display(), err);
+                return bindings;
+            }
+        };
+
+        bindings.apply(settings);
+        info!("Loaded key bindings from {}", path.display());
+        bindings
+    }
+
+    /// Rebinds every action in `settings`, an action with a binding that doesn't parse
+    /// keeps its defaults.
+    fn apply(&mut self, settings: Settings) {
+        for (action, names) in settings.bindings {
+            match names
+                .iter()
+                .map(|name| name.parse())
+                .collect::<Result<Vec<Binding>, _>>()
+            {
+                Ok(rebound) => {
+                    self.actions.insert(action, rebound);
+                }
+                Err(err) => warn!("Keeping the default for {:?}: {}", action, err),
+            }
+        }
+    }
+
+    fn bindings(&self, action: Action) -> &[Binding] {
+        self.actions
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn just_pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> bool {
+        self.bindings(action).iter().any(|binding| {
+            binding.check(
+                keys,
+                mouse,
+                |keys, key| keys.just_pressed(key),
+                |mouse, button| mouse.just_pressed(button),
+            )
+        })
+    }
+
+    pub fn pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> bool {
+        self.bindings(action).iter().any(|binding| {
+            binding.check(
+                keys,
+                mouse,
+                |keys, key| keys.pressed(key),
+                |mouse, button| mouse.pressed(button),
+            )
+        })
+    }
+
+    fn label(&self, action: Action) -> String {
+        match self.bindings(action) {
+            [] => "-".to_string(),
+            bindings => bindings
+                .iter()
+                .map(Binding::label)
+                .collect::<Vec<_>>()
+                .join("/"),
+        }
+    }
+
+    /// One line describing the active bindings.
+    pub fn help_text(&self, slots: usize) -> String {
+        let mut parts = vec![format!(
+            "({}-{}/Wheel) Hotbar",
+            self.label(Action::SelectSlot(0)),
+            self.label(Action::SelectSlot(slots.saturating_sub(1) as u8))
+        )];
+
+        for help in HELP {
+            parts.push(match help {
+                Help::Actions(actions, name) => {
+                    let keys: Vec<String> =
+                        actions.iter().map(|action| self.label(*action)).collect();
+                    format!("({}) {}", keys.join("/"), name)
+                }
+                Help::Fixed(text) => text.to_string(),
+            });
+        }
+        parts.join("  ")
+    }
+}
+
+/// The active bindings together with the input they are checked against.
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    bindings: Res<'w, KeyBindings>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+}
+
+impl Actions<'_> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings.just_pressed(action, &self.keys, &self.mouse)
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings.pressed(action, &self.keys, &self.mouse)
+    }
+}
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyBindings::load(Path::new(SETTINGS_FILE)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_overrides(text: &str) -> Result<KeyBindings, ron::error::SpannedError> {
+        let mut bindings = KeyBindings::default();
+        bindings.apply(ron::from_str(text)?);
+        Ok(bindings)
+    }
+
+    #[test]
+    fn parses_modifier_chords() {
+        assert_eq!(
+            "Ctrl+KeyZ".parse(),
+            Ok(Binding::key(KeyCode::KeyZ).with_ctrl())
+        );
+        assert_eq!(
+            "Alt + MouseRight".parse(),
+            Ok(Binding::mouse(MouseButton::Right).with_alt())
+        );
+        assert!("Super+KeyZ".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn unknown_actions_reject_the_file() {
+        assert!(with_overrides(r#"(bindings: { Fly: ["KeyF"] })"#).is_err());
+    }
+
+    #[test]
+    fn unknown_keys_keep_the_default_of_that_action_only() {
+        let bindings =
+            with_overrides(r#"(bindings: { ToggleRun: ["KeyP", "Hyper"], Save: ["F2"] })"#)
+                .unwrap();
+        assert_eq!(
+            bindings.bindings(Action::ToggleRun),
+            [Binding::key(KeyCode::Tab)]
+        );
+        assert_eq!(bindings.bindings(Action::Save), [Binding::key(KeyCode::F2)]);
+    }
+
+    #[test]
+    fn overrides_leave_other_actions_alone() {
+        let bindings = with_overrides(
+            r#"(bindings: { RotateCamera: ["Alt+MouseRight"], SelectSlot(0): ["KeyQ"] })"#,
+        )
+        .unwrap();
+        let defaults = KeyBindings::default();
+
+        assert_eq!(
+            bindings.bindings(Action::RotateCamera),
+            [Binding::mouse(MouseButton::Right).with_alt()]
+        );
+        assert_eq!(
+            bindings.bindings(Action::SelectSlot(0)),
+            [Binding::key(KeyCode::KeyQ)]
+        );
+        for action in [
+            Action::SelectSlot(1),
+            Action::PanCamera,
+            Action::OpenConsole,
+        ] {
+            assert_eq!(bindings.bindings(action), defaults.bindings(action));
+        }
+    }
+
+    #[test]
+    fn help_text_shows_the_active_bindings() {
+        let defaults = KeyBindings::default().help_text(9);
+        assert!(defaults.contains("(Tab) Run/Pause"));
+        assert!(defaults.contains("(1-9/Wheel) Hotbar"));
+
+        let bindings = with_overrides(
+            r#"(bindings: { ToggleRun: ["KeyP", "Shift+Space"], SelectSlot(8): ["KeyO"] })"#,
+        )
+        .unwrap();
+        let help = bindings.help_text(9);
+        assert!(help.contains("(P/Shift+Space) Run/Pause"), "{}", help);
+        assert!(help.contains("(1-O/Wheel) Hotbar"), "{}", help);
+    }
+}
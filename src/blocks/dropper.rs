@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use crate::{
+    RenderCtx, TextureAtlas,
+    blocks::{Block, BlockType, NeighbourUpdate, Placement, RecomputedResult, Renderable},
+    grid_plugin::{Activation, Grid},
+    interactions::{track_grid_cordinate, track_hovered_block, untrack_hovered_block},
+    meshes::MeshId,
+};
+
+/// There are no items to drop, so all that is modelled is the `triggered` flag Minecraft
+/// keeps to fire once per rising edge. Droppers are quasi-connected and only look at
+/// their power when they receive a neighbour update, which makes them the classic BUD.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Dropper {
+    pub facing: IVec3,
+    pub triggered: bool,
+}
+
+impl Block for Dropper {
+    fn on_placement(&self, grid: &Grid, position: IVec3, normal: IVec3) -> RecomputedResult<'_> {
+        let Some(_) = grid.get(position) else {
+            return RecomputedResult::Changed {
+                new_block: Some(BlockType::Dropper(Dropper {
+                    facing: normal,
+                    triggered: grid.is_activated(position, self.activation()),
+                })),
+                visual_update: true,
+                self_tick: None,
+                neighbor_tick: NeighbourUpdate::DEFAULT,
+            };
+        };
+        RecomputedResult::Unchanged
+    }
+
+    fn neighbor_changed(&self, grid: &Grid, position: IVec3) -> RecomputedResult<'_> {
+        let triggered = grid.is_activated(position, self.activation());
+        if triggered == self.triggered {
+            return RecomputedResult::Unchanged;
+        }
+
+        RecomputedResult::Changed {
+            new_block: Some(BlockType::Dropper(Dropper { triggered, ..*self })),
+            visual_update: false,
+            self_tick: None,
+            neighbor_tick: NeighbourUpdate::NONE,
+        }
+    }
+
+    fn try_place(&self, _grid: &Grid, _position: IVec3) -> bool {
+        true
+    }
+
+    fn activation(&self) -> Activation {
+        Activation::QuasiConnected
+    }
+
+    fn placement(&self) -> Placement {
+        Placement::PlayerFacing
+    }
+
+    fn turned(&self, _grid: &Grid, _position: IVec3, facing: IVec3) -> Option<BlockType> {
+        Some(BlockType::Dropper(Dropper { facing, ..*self }))
+    }
+}
+
+impl Renderable for Dropper {
+    fn spawn(&self, ctx: &mut RenderCtx, position: IVec3) {
+        let mesh = ctx
+            .mesh_registry
+            .get(MeshId::Dropper)
+            .expect("Could not load Dropper Mesh from registry");
+
+        let texture = ctx.atlas.handles.get(&TextureAtlas::Blocks);
+
+        let entity = ctx
+            .commands
+            .spawn((
+                Name::new("Dropper"),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(ctx.materials.add(StandardMaterial {
+                    base_color_texture: texture.cloned(),
+                    perceptual_roughness: 1.0,
+                    ..default()
+                })),
+                Transform::from_translation(position.as_vec3()),
+                Pickable {
+                    is_hoverable: true,
+                    ..default()
+                },
+            ))
+            .observe(track_hovered_block)
+            .observe(track_grid_cordinate)
+            .observe(untrack_hovered_block)
+            .id();
+        ctx.block_entities.entities.insert(position, entity);
+    }
+
+    fn update(&self, ctx: &mut RenderCtx, entity: Entity, _position: IVec3) {
+        let mesh = ctx
+            .mesh_registry
+            .get(MeshId::Dropper)
+            .expect("Could not load Dropper Mesh from registry");
+        ctx.commands.entity(entity).insert((Mesh3d(mesh.clone()),));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::{Dirt, RedStone},
+        testing::TestWorld,
+    };
+
+    const DROPPER: IVec3 = IVec3::ZERO;
+
+    fn triggered(world: &TestWorld) -> bool {
+        match world.grid().get_blocktype(DROPPER) {
+            Some(BlockType::Dropper(dropper)) => dropper.triggered,
+            block_type => panic!("expected a dropper, found {:?}", block_type),
+        }
+    }
+
+    /// A redstone block two above the dropper powers the space above it, but its
+    /// neighbour updates don't reach the dropper.
+    fn powered_above(quasi_connectivity: bool) -> TestWorld {
+        let mut world = TestWorld::new();
+        world.rules_mut().quasi_connectivity = quasi_connectivity;
+        world.place(BlockType::Dropper(Dropper::default()), DROPPER, IVec3::Y);
+        world.run(3);
+        world.place(BlockType::RedStone(RedStone), IVec3::new(0, 2, 0), IVec3::Y);
+        world.run(3);
+        world
+    }
+
+    #[test]
+    fn quasi_connected_dropper_waits_for_an_update() {
+        let mut world = powered_above(true);
+        assert!(
+            world
+                .grid()
+                .is_activated(DROPPER, Activation::QuasiConnected)
+        );
+        assert!(!triggered(&world), "nothing updated the dropper yet");
+
+        world.place(BlockType::Dirt(Dirt), IVec3::X, IVec3::Y);
+        world.run(3);
+        assert!(triggered(&world));
+    }
+
+    #[test]
+    fn dropper_ignores_power_above_without_quasi_connectivity() {
+        let mut world = powered_above(false);
+        assert!(
+            !world
+                .grid()
+                .is_activated(DROPPER, Activation::QuasiConnected)
+        );
+
+        world.place(BlockType::Dirt(Dirt), IVec3::X, IVec3::Y);
+        world.run(3);
+        assert!(!triggered(&world));
+
+        world.place(BlockType::RedStone(RedStone), IVec3::NEG_X, IVec3::Y);
+        world.run(3);
+        assert!(triggered(&world), "direct power still counts");
+    }
+}
@@ -22,6 +22,15 @@ pub struct Dust {
     power: u8,
 }
 
+impl Dust {
+    pub fn with_power(&self, power: u8) -> Self {
+        Self {
+            shape: self.shape,
+            power,
+        }
+    }
+}
+
 impl Block for Dust {
     fn on_placement(&self, grid: &Grid, position: IVec3, _normal: IVec3) -> RecomputedResult<'_> {
         let junction = resolve_junction(position, grid);
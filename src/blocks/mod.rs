@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 
 use crate::{
-    Grid, RenderCtx, block_position::BlockPos, grid_plugin::BlockChangeQueue,
-    redstone::NotifyDelay, render::DirtyBlocks,
+    Grid, RenderCtx,
+    block_position::BlockPos,
+    grid_plugin::{Activation, BlockChangeQueue},
+    redstone::NotifyDelay,
+    render::DirtyBlocks,
 };
 
 mod dirt;
+mod dropper;
 mod dust;
 mod redstone_block;
 mod redstone_lamp;
@@ -13,6 +17,7 @@ mod redstone_torch;
 mod standard_grass;
 
 pub use dirt::Dirt;
+pub use dropper::Dropper;
 pub use dust::Dust;
 pub use redstone_block::RedStone;
 pub use redstone_lamp::RedStoneLamp;
@@ -30,11 +35,51 @@ pub const ALL_DIRS: &[IVec3; 6] = &[
 
 pub const DIRS: &[IVec3; 4] = &[IVec3::Z, IVec3::NEG_Z, IVec3::X, IVec3::NEG_X];
 
+/// How a block picks its facing when placed, unless the player turned it beforehand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// No facing at all.
+    Fixed,
+    /// Attached to the face that was clicked, like torches and buttons.
+    ClickedFace,
+    /// Pointing the way the camera looks, level with the ground, like droppers and
+    /// diodes.
+    PlayerFacing,
+}
+
+impl Placement {
+    /// Facings the rotate key cycles through.
+    pub fn facings(&self) -> &'static [IVec3] {
+        match self {
+            Placement::Fixed => &[],
+            Placement::ClickedFace => &[IVec3::Y, IVec3::NEG_Z, IVec3::X, IVec3::Z, IVec3::NEG_X],
+            Placement::PlayerFacing => &[IVec3::NEG_Z, IVec3::X, IVec3::Z, IVec3::NEG_X],
+        }
+    }
+}
+
 pub trait Block {
+    /// `normal` is the facing the block should get, the clicked face unless the
+    /// player turned the block before placing it.
     fn on_placement(&self, grid: &Grid, position: IVec3, normal: IVec3) -> RecomputedResult<'_>;
+    /// Called once per tick for a block that received a [`NeighbourUpdate`], or sits next
+    /// to a [`BlockChange::NotifyNeighbours`](crate::grid_plugin::BlockChange). Changes
+    /// further away never reach it, which is what leaves quasi-connected blocks in a
+    /// BUD state.
     fn neighbor_changed(&self, grid: &Grid, position: IVec3) -> RecomputedResult<'_>;
     fn try_place(&self, grid: &Grid, position: IVec3) -> bool;
     fn on_remove(&self, _grid: &Grid, _position: &BlockPos, _queue: &mut BlockChangeQueue) {}
+    fn activation(&self) -> Activation {
+        Activation::Direct
+    }
+    fn placement(&self) -> Placement {
+        Placement::Fixed
+    }
+    /// The block turned to `facing` where it stands, without breaking it. `None` when
+    /// it has no facing.
+    fn turned(&self, _grid: &Grid, _position: IVec3, _facing: IVec3) -> Option<BlockType> {
+        None
+    }
 }
 
 pub trait Tickable {
@@ -56,6 +101,7 @@ pub enum BlockType {
     RedStoneLamp(RedStoneLamp),
     RedStoneTorch(RedStoneTorch),
     Dust(Dust),
+    Dropper(Dropper),
     // StoneButton {
     //     pressed: bool,
     //     attached_face: IVec3,
@@ -87,6 +133,37 @@ impl BlockType {
         }
     }
 
+    /// Which way a directional block points, in the same terms as [`Placement`].
+    pub fn facing(&self) -> Option<IVec3> {
+        match self {
+            BlockType::RedStoneTorch(torch) => Some(torch.attached_face),
+            BlockType::Dropper(dropper) => Some(dropper.facing),
+            _ => None,
+        }
+    }
+
+    /// The block after turning or mirroring its surroundings with `transform`, used when
+    /// pasting transformed copies. Anything with a direction has to turn along, diodes
+    /// included once they exist.
+    pub fn transformed(&self, transform: impl Fn(IVec3) -> IVec3) -> BlockType {
+        match self {
+            BlockType::RedStoneTorch(torch) => BlockType::RedStoneTorch(RedStoneTorch {
+                attached_face: transform(torch.attached_face),
+                ..*torch
+            }),
+            BlockType::Dust(dust) => {
+                let mut dust = *dust;
+                dust.shape = dust.shape.transformed(transform);
+                BlockType::Dust(dust)
+            }
+            BlockType::Dropper(dropper) => BlockType::Dropper(Dropper {
+                facing: transform(dropper.facing),
+                ..*dropper
+            }),
+            block_type => *block_type,
+        }
+    }
+
     pub fn weak_power_emitted(
         &self,
         asking_pos: IVec3,
@@ -95,9 +172,13 @@ impl BlockType {
     ) -> u8 {
         match &self {
             BlockType::Dust(block) => block.power(),
-            BlockType::RedStoneTorch(RedStoneTorch { attached_face, .. }) => {
+            BlockType::RedStoneTorch(RedStoneTorch { lit, attached_face }) => {
                 let attached_pos = emitting_pos - attached_face;
-                if asking_pos == attached_pos { 0 } else { 15 }
+                if !lit || asking_pos == attached_pos {
+                    0
+                } else {
+                    15
+                }
             }
             _ => 0,
         }
@@ -330,6 +411,7 @@ impl Block for BlockType {
             BlockType::RedStoneLamp(block) => block.on_placement(grid, position, normal),
             BlockType::RedStoneTorch(block) => block.on_placement(grid, position, normal),
             BlockType::Dust(block) => block.on_placement(grid, position, normal),
+            BlockType::Dropper(block) => block.on_placement(grid, position, normal),
         }
     }
 
@@ -341,6 +423,7 @@ impl Block for BlockType {
             BlockType::RedStoneLamp(block) => block.neighbor_changed(grid, position),
             BlockType::Dust(block) => block.neighbor_changed(grid, position),
             BlockType::RedStoneTorch(block) => block.neighbor_changed(grid, position),
+            BlockType::Dropper(block) => block.neighbor_changed(grid, position),
             _ => todo!("recalculate has not been implemented for: {:?}", self),
         }
     }
@@ -353,6 +436,7 @@ impl Block for BlockType {
             BlockType::RedStoneLamp(block) => block.try_place(grid, position),
             BlockType::Dust(block) => block.try_place(grid, position),
             BlockType::RedStoneTorch(block) => block.try_place(grid, position),
+            BlockType::Dropper(block) => block.try_place(grid, position),
             _ => todo!("try_place has not been implemented for: {:?}", self),
         }
     }
@@ -363,6 +447,29 @@ impl Block for BlockType {
             _ => {}
         }
     }
+
+    fn activation(&self) -> Activation {
+        match self {
+            BlockType::Dropper(block) => block.activation(),
+            _ => Activation::Direct,
+        }
+    }
+
+    fn placement(&self) -> Placement {
+        match self {
+            BlockType::RedStoneTorch(block) => block.placement(),
+            BlockType::Dropper(block) => block.placement(),
+            _ => Placement::Fixed,
+        }
+    }
+
+    fn turned(&self, grid: &Grid, position: IVec3, facing: IVec3) -> Option<BlockType> {
+        match self {
+            BlockType::RedStoneTorch(block) => block.turned(grid, position, facing),
+            BlockType::Dropper(block) => block.turned(grid, position, facing),
+            _ => None,
+        }
+    }
 }
 
 impl Tickable for BlockType {
@@ -370,6 +477,7 @@ impl Tickable for BlockType {
         match self {
             BlockType::RedStoneLamp(block) => block.on_tick(grid, position),
             BlockType::Dust(block) => block.on_tick(grid, position),
+            BlockType::RedStoneTorch(block) => block.on_tick(grid, position),
             _ => RecomputedResult::Unchanged,
         }
     }
@@ -394,6 +502,7 @@ impl Renderable for BlockType {
             BlockType::RedStoneLamp(red_stone_lamp) => red_stone_lamp.spawn(ctx, position),
             BlockType::Dust(dust) => dust.spawn(ctx, position),
             BlockType::RedStoneTorch(block) => block.spawn(ctx, position),
+            BlockType::Dropper(block) => block.spawn(ctx, position),
         }
     }
 
@@ -408,6 +517,7 @@ impl Renderable for BlockType {
             BlockType::RedStoneLamp(red_stone_lamp) => red_stone_lamp.update(ctx, entity, position),
             BlockType::Dust(dust) => dust.update(ctx, entity, position),
             BlockType::RedStoneTorch(block) => block.update(ctx, entity, position),
+            BlockType::Dropper(block) => block.update(ctx, entity, position),
         }
     }
 }
@@ -16,6 +16,10 @@ pub struct RedStoneLamp {
 }
 
 impl RedStoneLamp {
+    pub fn with_power(&self, power: u8) -> Self {
+        Self { power }
+    }
+
     fn resolve_power(&self, grid: &Grid, position: IVec3) -> u8 {
         let mut new_power = 0;
         for dir in ALL_DIRS {
@@ -2,7 +2,9 @@ use bevy::{prelude::*, render::render_resource::Face};
 
 use crate::{
     TextureAtlas,
-    blocks::{Block, BlockType, NeighbourUpdate, RecomputedResult, Renderable, Tickable},
+    blocks::{
+        Block, BlockType, NeighbourUpdate, Placement, RecomputedResult, Renderable, Tickable,
+    },
     grid_plugin::Grid,
     interactions::{track_grid_cordinate, track_hovered_block, untrack_hovered_block},
     meshes::MeshId,
@@ -15,6 +17,30 @@ pub struct RedStoneTorch {
     pub attached_face: IVec3,
 }
 
+impl RedStoneTorch {
+    /// Wall torches lean away from the block they are attached to.
+    pub fn transform(&self, position: IVec3) -> Transform {
+        let slant_pos = (25.0_f32).to_radians();
+        let slant_neg = (-25.0_f32).to_radians();
+        let distance = 0.37;
+        match self.attached_face {
+            IVec3::X => Transform::from_translation(position.as_vec3() - Vec3::X * distance)
+                .with_rotation(Quat::from_rotation_z(slant_neg)),
+            IVec3::NEG_X => {
+                Transform::from_translation(position.as_vec3() - Vec3::NEG_X * distance)
+                    .with_rotation(Quat::from_rotation_z(slant_pos))
+            }
+            IVec3::Z => Transform::from_translation(position.as_vec3() - Vec3::Z * distance)
+                .with_rotation(Quat::from_rotation_x(slant_pos)),
+            IVec3::NEG_Z => {
+                Transform::from_translation(position.as_vec3() - Vec3::NEG_Z * distance)
+                    .with_rotation(Quat::from_rotation_x(slant_neg))
+            }
+            _ => Transform::from_translation(position.as_vec3() - Vec3::Y * 0.25),
+        }
+    }
+}
+
 impl Block for RedStoneTorch {
     fn on_placement(&self, grid: &Grid, position: IVec3, normal: IVec3) -> RecomputedResult<'_> {
         let attached_block_has_power = grid.get_direct_signal(position - normal) > 0;
@@ -41,12 +67,13 @@ impl Block for RedStoneTorch {
         let attached_block_has_power = grid.get_direct_signal(position - self.attached_face) > 0;
 
         if self.lit == attached_block_has_power {
-            // If power has changed, schedule for next tick, but dont update the torch state yet
+            // If power has changed, schedule for next tick, but dont update the torch state yet.
+            // A change without a block removes it, so the torch is placed again as it is
             return RecomputedResult::Changed {
-                new_block: None,
+                new_block: Some(BlockType::RedStoneTorch(*self)),
                 visual_update: false,
                 self_tick: Some(NotifyDelay::NextTick),
-                neighbor_tick: NeighbourUpdate::DEFAULT,
+                neighbor_tick: NeighbourUpdate::NONE,
             };
         }
 
@@ -56,6 +83,17 @@ impl Block for RedStoneTorch {
     fn try_place(&self, _grid: &crate::grid_plugin::Grid, _position: IVec3) -> bool {
         true
     }
+
+    fn placement(&self) -> Placement {
+        Placement::ClickedFace
+    }
+
+    fn turned(&self, grid: &Grid, position: IVec3, facing: IVec3) -> Option<BlockType> {
+        Some(BlockType::RedStoneTorch(RedStoneTorch {
+            lit: grid.get_direct_signal(position - facing) == 0,
+            attached_face: facing,
+        }))
+    }
 }
 
 impl Tickable for RedStoneTorch {
@@ -105,26 +143,7 @@ impl Renderable for RedStoneTorch {
                 .expect("Could not load redstone torch stem")
         };
 
-        let transform = {
-            let slant_pos = (25.0_f32).to_radians();
-            let slant_neg = (-25.0_f32).to_radians();
-            let distance = 0.37;
-            match block.attached_face {
-                IVec3::X => Transform::from_translation(position.as_vec3() - Vec3::X * distance)
-                    .with_rotation(Quat::from_rotation_z(slant_neg)),
-                IVec3::NEG_X => {
-                    Transform::from_translation(position.as_vec3() - Vec3::NEG_X * distance)
-                        .with_rotation(Quat::from_rotation_z(slant_pos))
-                }
-                IVec3::Z => Transform::from_translation(position.as_vec3() - Vec3::Z * distance)
-                    .with_rotation(Quat::from_rotation_x(slant_pos)),
-                IVec3::NEG_Z => {
-                    Transform::from_translation(position.as_vec3() - Vec3::NEG_Z * distance)
-                        .with_rotation(Quat::from_rotation_x(slant_neg))
-                }
-                _ => Transform::from_translation(position.as_vec3() - Vec3::Y * 0.25),
-            }
-        };
+        let transform = block.transform(position);
 
         let entity = ctx
             .commands
@@ -175,7 +194,10 @@ impl Renderable for RedStoneTorch {
         ctx.block_entities.entities.insert(position, entity);
     }
 
-    fn update(&self, _ctx: &mut crate::RenderCtx, _entity: Entity, _position: IVec3) {
-        todo!()
+    /// The stem, glow and slant all depend on the state, so the torch is respawned.
+    fn update(&self, ctx: &mut crate::RenderCtx, entity: Entity, position: IVec3) {
+        ctx.commands.entity(entity).despawn();
+        ctx.block_entities.entities.remove(&position);
+        self.spawn(ctx, position);
     }
 }
@@ -0,0 +1,491 @@
+use bevy::{math::I64Vec3, platform::collections::HashMap, prelude::*};
+use std::ops::Range;
+
+use crate::{BlockData, BlockType};
+
+pub const CHUNK_SIZE: i32 = 16;
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+const MIN_BITS: u32 = 4;
+
+/// Position of the chunk containing the block at `pos`.
+pub fn chunk_coords(pos: IVec3) -> IVec3 {
+    pos.div_euclid(IVec3::splat(CHUNK_SIZE))
+}
+
+fn local_index(pos: IVec3) -> usize {
+    let local = pos.rem_euclid(IVec3::splat(CHUNK_SIZE));
+    (local.x + local.z * CHUNK_SIZE + local.y * CHUNK_SIZE * CHUNK_SIZE) as usize
+}
+
+fn local_position(index: usize) -> IVec3 {
+    let index = index as i32;
+    IVec3::new(
+        index % CHUNK_SIZE,
+        index / (CHUNK_SIZE * CHUNK_SIZE),
+        (index / CHUNK_SIZE) % CHUNK_SIZE,
+    )
+}
+
+/// Palette indices packed into 64 bit words, entries never straddle two words.
+struct PackedArray {
+    bits: u32,
+    words: Vec<u64>,
+}
+
+impl PackedArray {
+    fn new(bits: u32) -> Self {
+        let per_word = (64 / bits) as usize;
+        Self {
+            bits,
+            words: vec![0; CHUNK_VOLUME.div_ceil(per_word)],
+        }
+    }
+
+    fn get(&self, index: usize) -> u16 {
+        let per_word = (64 / self.bits) as usize;
+        let shift = (index % per_word) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+        ((self.words[index / per_word] >> shift) & mask) as u16
+    }
+
+    fn set(&mut self, index: usize, value: u16) {
+        let per_word = (64 / self.bits) as usize;
+        let shift = (index % per_word) as u32 * self.bits;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let word = &mut self.words[index / per_word];
+        *word = (*word & !mask) | ((value as u64) << shift);
+    }
+
+    /// Indices within `indices` holding anything but 0, words that are all 0 are
+    /// skipped without decoding them.
+    fn occupied(&self, indices: Range<usize>) -> impl Iterator<Item = usize> + '_ {
+        let per_word = (64 / self.bits) as usize;
+        let mut index = indices.start;
+        std::iter::from_fn(move || {
+            while index < indices.end {
+                let current = index;
+                if self.words[current / per_word] == 0 {
+                    index = (current / per_word + 1) * per_word;
+                    continue;
+                }
+                index += 1;
+                if self.get(current) != 0 {
+                    return Some(current);
+                }
+            }
+            None
+        })
+    }
+
+    fn resized(&self, bits: u32) -> Self {
+        let mut resized = Self::new(bits);
+        for index in 0..CHUNK_VOLUME {
+            resized.set(index, self.get(index));
+        }
+        resized
+    }
+}
+
+struct PaletteEntry {
+    data: Option<BlockData>,
+    count: u32,
+}
+
+/// A 16³ section of the grid. Blocks are stored as indices into a palette of the
+/// distinct block states in the chunk, palette entry 0 is always empty.
+pub struct Chunk {
+    origin: IVec3,
+    palette: Vec<PaletteEntry>,
+    lookup: HashMap<BlockType, u16>,
+    free: Vec<u16>,
+    indices: PackedArray,
+    len: usize,
+    revision: u64,
+}
+
+impl Chunk {
+    fn new(coords: IVec3) -> Self {
+        Self {
+            origin: coords * CHUNK_SIZE,
+            palette: vec![PaletteEntry {
+                data: None,
+                count: 0,
+            }],
+            lookup: HashMap::default(),
+            free: Vec::new(),
+            indices: PackedArray::new(MIN_BITS),
+            len: 0,
+            revision: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn get(&self, pos: IVec3) -> Option<&BlockData> {
+        let id = self.indices.get(local_index(pos));
+        self.palette[id as usize].data.as_ref()
+    }
+
+    fn insert(&mut self, pos: IVec3, data: BlockData) {
+        let index = local_index(pos);
+        let id = self.palette_id(data);
+        self.replace(index, id);
+    }
+
+    fn remove(&mut self, pos: IVec3) {
+        self.replace(local_index(pos), 0);
+    }
+
+    fn replace(&mut self, index: usize, id: u16) {
+        let old = self.indices.get(index);
+        self.indices.set(index, id);
+
+        match (old, id) {
+            (0, 0) => {}
+            (0, _) => self.len += 1,
+            (_, 0) => self.len -= 1,
+            _ => {}
+        }
+
+        self.release(old);
+    }
+
+    fn palette_id(&mut self, data: BlockData) -> u16 {
+        if let Some(&id) = self.lookup.get(&data.block_type) {
+            self.palette[id as usize].count += 1;
+            return id;
+        }
+
+        let block_type = data.block_type;
+        let entry = PaletteEntry {
+            data: Some(data),
+            count: 1,
+        };
+
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.palette[id as usize] = entry;
+                id
+            }
+            None => {
+                self.palette.push(entry);
+                let id = (self.palette.len() - 1) as u16;
+                if self.palette.len() > 1 << self.indices.bits {
+                    self.indices = self.indices.resized(self.indices.bits + 1);
+                }
+                id
+            }
+        };
+
+        self.lookup.insert(block_type, id);
+        id
+    }
+
+    fn release(&mut self, id: u16) {
+        if id == 0 {
+            return;
+        }
+
+        let entry = &mut self.palette[id as usize];
+        entry.count -= 1;
+        if entry.count == 0
+            && let Some(data) = entry.data.take()
+        {
+            self.lookup.remove(&data.block_type);
+            self.free.push(id);
+        }
+    }
+
+    /// All blocks in the chunk with their world position.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        self.cells(IVec3::ZERO, IVec3::splat(CHUNK_SIZE - 1))
+    }
+
+    /// Blocks between the local positions `lo` and `hi`, inclusive, walked row by row
+    /// so empty stretches of a row cost one word each.
+    fn cells(&self, lo: IVec3, hi: IVec3) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        (lo.y..=hi.y)
+            .flat_map(move |y| (lo.z..=hi.z).map(move |z| (y, z)))
+            .flat_map(move |(y, z)| {
+                let row = (z * CHUNK_SIZE + y * CHUNK_SIZE * CHUNK_SIZE) as usize;
+                self.indices
+                    .occupied(row + lo.x as usize..row + hi.x as usize + 1)
+            })
+            .filter_map(move |index| {
+                let id = self.indices.get(index);
+                self.palette[id as usize]
+                    .data
+                    .as_ref()
+                    .map(|data| (self.origin + local_position(index), data))
+            })
+    }
+}
+
+/// Sparse collection of chunks, only chunks containing at least one block are kept.
+#[derive(Default)]
+pub struct ChunkMap {
+    chunks: HashMap<IVec3, Chunk>,
+    revision: u64,
+}
+
+impl ChunkMap {
+    pub fn get(&self, pos: IVec3) -> Option<&BlockData> {
+        self.chunks.get(&chunk_coords(pos))?.get(pos)
+    }
+
+    pub fn insert(&mut self, pos: IVec3, data: BlockData) {
+        let coords = chunk_coords(pos);
+        self.revision += 1;
+        let chunk = self
+            .chunks
+            .entry(coords)
+            .or_insert_with(|| Chunk::new(coords));
+        chunk.insert(pos, data);
+        chunk.revision = self.revision;
+    }
+
+    pub fn remove(&mut self, pos: IVec3) {
+        let coords = chunk_coords(pos);
+        let Some(chunk) = self.chunks.get_mut(&coords) else {
+            return;
+        };
+
+        self.revision += 1;
+        chunk.remove(pos);
+        chunk.revision = self.revision;
+        if chunk.is_empty() {
+            self.chunks.remove(&coords);
+        }
+    }
+
+    /// Bumped whenever a block in the chunk changes, revisions are never reused, not
+    /// even by a chunk that was emptied and filled again.
+    pub fn revision(&self, coords: IVec3) -> Option<u64> {
+        self.chunks.get(&coords).map(|chunk| chunk.revision)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (&IVec3, &Chunk)> {
+        self.chunks.iter()
+    }
+
+    /// Blocks inside the inclusive box spanned by `min` and `max`, chunk by chunk in
+    /// y, z, x order. Boxes spanning more chunk positions than there are chunks look
+    /// through the chunks instead, and only the part of each chunk inside the box is
+    /// read.
+    pub fn range(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        let (min, max) = (min.min(max), min.max(max));
+        let (chunk_min, chunk_max) = (chunk_coords(min), chunk_coords(max));
+
+        let span = chunk_max.as_i64vec3() - chunk_min.as_i64vec3() + I64Vec3::ONE;
+        let spanned = span.x.saturating_mul(span.y).saturating_mul(span.z);
+        let mut chunks: Vec<&Chunk> = if spanned > self.chunks.len() as i64 {
+            self.chunks
+                .iter()
+                .filter(|(coords, _)| {
+                    coords.cmpge(chunk_min).all() && coords.cmple(chunk_max).all()
+                })
+                .map(|(_, chunk)| chunk)
+                .collect()
+        } else {
+            (chunk_min.y..=chunk_max.y)
+                .flat_map(|y| {
+                    (chunk_min.z..=chunk_max.z).flat_map(move |z| {
+                        (chunk_min.x..=chunk_max.x).map(move |x| IVec3::new(x, y, z))
+                    })
+                })
+                .filter_map(|coords| self.chunks.get(&coords))
+                .collect()
+        };
+        chunks.sort_by_key(|chunk| (chunk.origin.y, chunk.origin.z, chunk.origin.x));
+
+        chunks.into_iter().flat_map(move |chunk| {
+            let lo = min.saturating_sub(chunk.origin).max(IVec3::ZERO);
+            let hi = max
+                .saturating_sub(chunk.origin)
+                .min(IVec3::splat(CHUNK_SIZE - 1));
+            chunk.cells(lo, hi)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{Dirt, Dust, RedStoneLamp};
+
+    fn data(block_type: BlockType) -> BlockData {
+        BlockData { block_type }
+    }
+
+    /// Distinct block states, enough to outgrow any palette width in the tests.
+    fn dust(power: u8) -> BlockData {
+        data(dust_type(power))
+    }
+
+    fn dust_type(power: u8) -> BlockType {
+        BlockType::Dust(Dust::default().with_power(power))
+    }
+
+    #[test]
+    fn packed_array_keeps_values_when_widened() {
+        let mut array = PackedArray::new(MIN_BITS);
+        for index in 0..CHUNK_VOLUME {
+            array.set(index, (index % 16) as u16);
+        }
+
+        for bits in MIN_BITS + 1..=9 {
+            array = array.resized(bits);
+            let max = (1 << bits) - 1;
+            array.set(CHUNK_VOLUME - 1, max);
+            for index in 0..CHUNK_VOLUME - 1 {
+                assert_eq!(array.get(index), (index % 16) as u16, "{} bits", bits);
+            }
+            assert_eq!(array.get(CHUNK_VOLUME - 1), max);
+            array.set(CHUNK_VOLUME - 1, 15);
+        }
+    }
+
+    #[test]
+    fn chunk_widens_its_indices_past_sixteen_states() {
+        let mut chunk = Chunk::new(IVec3::ZERO);
+        for power in 0..15 {
+            chunk.insert(IVec3::new(power as i32, 0, 0), dust(power));
+        }
+        assert_eq!(
+            chunk.indices.bits, MIN_BITS,
+            "15 states and empty fit 4 bits"
+        );
+
+        chunk.insert(IVec3::new(0, 1, 0), data(BlockType::Dirt(Dirt)));
+        assert_eq!(chunk.indices.bits, MIN_BITS + 1);
+        for power in 0..15 {
+            let block = chunk.get(IVec3::new(power as i32, 0, 0));
+            assert_eq!(block.map(|data| data.block_type), Some(dust_type(power)));
+        }
+        let block = chunk.get(IVec3::new(0, 1, 0));
+        assert_eq!(
+            block.map(|data| data.block_type),
+            Some(BlockType::Dirt(Dirt))
+        );
+        assert_eq!(chunk.len, 16);
+    }
+
+    #[test]
+    fn freed_palette_entries_are_reused() {
+        let mut chunk = Chunk::new(IVec3::ZERO);
+        chunk.insert(IVec3::ZERO, dust(1));
+        chunk.insert(IVec3::X, dust(2));
+        chunk.insert(IVec3::Y, dust(2));
+        let entries = chunk.palette.len();
+
+        chunk.remove(IVec3::ZERO);
+        assert_eq!(chunk.free.len(), 1);
+        chunk.remove(IVec3::X);
+        assert_eq!(chunk.free.len(), 1, "dust(2) is still used once");
+
+        chunk.insert(
+            IVec3::Z,
+            data(BlockType::RedStoneLamp(RedStoneLamp::default())),
+        );
+        assert!(chunk.free.is_empty());
+        assert_eq!(chunk.palette.len(), entries);
+        let block = chunk.get(IVec3::Y);
+        assert_eq!(block.map(|data| data.block_type), Some(dust_type(2)));
+    }
+
+    #[test]
+    fn len_counts_occupied_cells() {
+        let mut chunk = Chunk::new(IVec3::ZERO);
+        chunk.insert(IVec3::ZERO, dust(1));
+        chunk.insert(IVec3::X, dust(1));
+        chunk.insert(IVec3::X, dust(3));
+        assert_eq!(chunk.len, 2, "replacing a block keeps the count");
+
+        chunk.remove(IVec3::ZERO);
+        chunk.remove(IVec3::Y);
+        assert_eq!(chunk.len, 1, "removing an empty cell keeps the count");
+        assert_eq!(chunk.iter().count(), 1);
+    }
+
+    #[test]
+    fn empty_chunks_are_dropped_and_revisions_only_grow() {
+        let mut map = ChunkMap::default();
+        let position = IVec3::new(3, 4, 5);
+        map.insert(position, dust(1));
+        let first = map.revision(IVec3::ZERO).expect("chunk exists");
+
+        map.remove(position);
+        assert_eq!(map.chunks().count(), 0);
+        assert_eq!(map.revision(IVec3::ZERO), None);
+
+        map.insert(position, dust(1));
+        assert!(map.revision(IVec3::ZERO).expect("chunk exists") > first);
+        map.insert(IVec3::new(40, 0, 0), dust(1));
+        let other = map.revision(IVec3::new(2, 0, 0)).expect("chunk exists");
+        assert!(other > map.revision(IVec3::ZERO).unwrap());
+    }
+
+    #[test]
+    fn negative_positions_map_to_their_own_chunks() {
+        assert_eq!(
+            chunk_coords(IVec3::new(-1, -16, -17)),
+            IVec3::new(-1, -1, -2)
+        );
+        assert_eq!(chunk_coords(IVec3::new(15, 16, 0)), IVec3::new(0, 1, 0));
+        assert_eq!(local_index(IVec3::new(-1, 0, 0)), 15);
+        assert_eq!(local_index(IVec3::new(0, -1, -1)), 15 * 16 + 15 * 256);
+        assert_eq!(
+            local_position(local_index(IVec3::new(-3, -7, -11))),
+            IVec3::new(13, 9, 5)
+        );
+
+        let mut map = ChunkMap::default();
+        map.insert(IVec3::new(-1, -1, -1), dust(1));
+        map.insert(IVec3::ZERO, dust(2));
+        let block = map.get(IVec3::new(-1, -1, -1));
+        assert_eq!(block.map(|data| data.block_type), Some(dust_type(1)));
+        let block = map.get(IVec3::ZERO);
+        assert_eq!(block.map(|data| data.block_type), Some(dust_type(2)));
+        assert_eq!(map.chunks().count(), 2);
+    }
+
+    #[test]
+    fn range_matches_a_full_scan() {
+        let mut map = ChunkMap::default();
+        let positions = [
+            IVec3::new(-20, 0, 0),
+            IVec3::new(-1, -1, -1),
+            IVec3::new(0, 0, 0),
+            IVec3::new(5, 3, 17),
+            IVec3::new(15, 15, 15),
+            IVec3::new(16, 0, 0),
+            IVec3::new(100, -50, 7),
+        ];
+        for (power, position) in positions.iter().enumerate() {
+            map.insert(*position, dust(power as u8));
+        }
+
+        let boxes = [
+            (IVec3::new(-1, -1, -1), IVec3::new(15, 15, 15)),
+            (IVec3::new(16, 0, 0), IVec3::new(-20, 3, 17)),
+            (IVec3::new(3, 2, 1), IVec3::new(6, 4, 20)),
+            (IVec3::splat(i32::MIN), IVec3::splat(i32::MAX)),
+            (IVec3::new(i32::MIN, -1, -1), IVec3::new(0, 0, 0)),
+        ];
+        for (min, max) in boxes {
+            let (lo, hi) = (min.min(max), min.max(max));
+            let mut expected: Vec<_> = map
+                .chunks()
+                .flat_map(|(_, chunk)| chunk.iter())
+                .filter(|(position, _)| position.cmpge(lo).all() && position.cmple(hi).all())
+                .map(|(position, _)| position)
+                .collect();
+            let mut found: Vec<_> = map.range(min, max).map(|(position, _)| position).collect();
+            expected.sort_by_key(|p| (p.x, p.y, p.z));
+            found.sort_by_key(|p| (p.x, p.y, p.z));
+            assert_eq!(found, expected, "box {} to {}", min, max);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+
+use super::MAX_VOLUME;
+use crate::{
+    blocks::BlockType,
+    redstone::Tick,
+    schematic::{BlockState, MappedState, to_block_type},
+};
+
+#[derive(Debug)]
+pub enum Command {
+    /// `None` clears the block.
+    SetBlock {
+        position: IVec3,
+        block: Option<BlockType>,
+    },
+    Fill {
+        from: IVec3,
+        to: IVec3,
+        block: Option<BlockType>,
+    },
+    Clone {
+        from: IVec3,
+        to: IVec3,
+        destination: IVec3,
+    },
+    /// Previews `count` copies of the selection, each shifted by the offset.
+    Stack {
+        count: u32,
+        offset: StackOffset,
+    },
+    StackApply,
+    StackCancel,
+    TickStep(Tick),
+    /// Shows the rate without a value.
+    TickRate(Option<f64>),
+}
+
+#[derive(Debug)]
+pub enum StackOffset {
+    /// Copies are placed side by side in this direction.
+    Direction(IVec3),
+    Vector(IVec3),
+}
+
+const USAGE: &str = "commands: setblock, fill, clone, stack, tick step, tick rate";
+
+/// Parses one console line, coordinates written as `~` or `~N` are relative to
+/// `origin`.
+pub fn parse(line: &str, origin: IVec3) -> Result<Command, String> {
+    let mut args = line.trim().trim_start_matches('/').split_whitespace();
+    let Some(name) = args.next() else {
+        return Err(USAGE.to_string());
+    };
+    let args: Vec<&str> = args.collect();
+
+    match (name, args.as_slice()) {
+        ("setblock", [x, y, z, block]) => Ok(Command::SetBlock {
+            position: position([*x, *y, *z], origin)?,
+            block: block_type(block)?,
+        }),
+        ("setblock", _) => Err("usage: setblock x y z <block[state]>".to_string()),
+        ("fill", [x1, y1, z1, x2, y2, z2, block]) => Ok(Command::Fill {
+            from: position([*x1, *y1, *z1], origin)?,
+            to: position([*x2, *y2, *z2], origin)?,
+            block: block_type(block)?,
+        }),
+        ("fill", _) => Err("usage: fill x1 y1 z1 x2 y2 z2 <block[state]>".to_string()),
+        ("clone", [x1, y1, z1, x2, y2, z2, x, y, z]) => Ok(Command::Clone {
+            from: position([*x1, *y1, *z1], origin)?,
+            to: position([*x2, *y2, *z2], origin)?,
+            destination: position([*x, *y, *z], origin)?,
+        }),
+        ("clone", _) => Err("usage: clone x1 y1 z1 x2 y2 z2 x y z".to_string()),
+        ("stack", ["apply"]) => Ok(Command::StackApply),
+        ("stack", ["cancel"]) => Ok(Command::StackCancel),
+        ("stack", [count, direction]) => Ok(Command::Stack {
+            count: stack_count(count)?,
+            offset: StackOffset::Direction(direction_of(direction)?),
+        }),
+        ("stack", [count, x, y, z]) => Ok(Command::Stack {
+            count: stack_count(count)?,
+            offset: StackOffset::Vector(position([*x, *y, *z], IVec3::ZERO)?),
+        }),
+        ("stack", _) => Err(
+            "usage: stack N <north|south|east|west|up|down> | stack N dx dy dz | stack apply | stack cancel"
+                .to_string(),
+        ),
+        ("tick", ["step"]) => Ok(Command::TickStep(1)),
+        ("tick", ["step", ticks]) => ticks
+            .parse()
+            .map(Command::TickStep)
+            .map_err(|_| format!("not a tick count: {}", ticks)),
+        ("tick", ["rate"]) => Ok(Command::TickRate(None)),
+        ("tick", ["rate", rate]) => match rate.parse::<f64>() {
+            Ok(rate) if (1.0..=1000.0).contains(&rate) => Ok(Command::TickRate(Some(rate))),
+            _ => Err(format!(
+                "tick rate must be between 1 and 1000, got {}",
+                rate
+            )),
+        },
+        ("tick", _) => Err("usage: tick step [N] | tick rate [N]".to_string()),
+        (name, _) => Err(format!("unknown command {}, {}", name, USAGE)),
+    }
+}
+
+/// Every copy places at least one block, so more copies than `MAX_VOLUME` can never
+/// be built.
+fn stack_count(count: &str) -> Result<u32, String> {
+    match count.parse() {
+        Ok(count) if count > 0 && count as i64 <= MAX_VOLUME => Ok(count),
+        Ok(_) => Err(format!("copy count must be between 1 and {}", MAX_VOLUME)),
+        Err(_) => Err(format!("not a copy count: {}", count)),
+    }
+}
+
+fn direction_of(direction: &str) -> Result<IVec3, String> {
+    match direction {
+        "north" => Ok(IVec3::NEG_Z),
+        "south" => Ok(IVec3::Z),
+        "east" => Ok(IVec3::X),
+        "west" => Ok(IVec3::NEG_X),
+        "up" => Ok(IVec3::Y),
+        "down" => Ok(IVec3::NEG_Y),
+        _ => Err(format!("not a direction: {}", direction)),
+    }
+}
+
+fn position(coordinates: [&str; 3], origin: IVec3) -> Result<IVec3, String> {
+    let mut position = [0; 3];
+    for (axis, coordinate) in coordinates.into_iter().enumerate() {
+        let invalid = || format!("not a coordinate: {}", coordinate);
+        position[axis] = match coordinate.strip_prefix('~') {
+            Some("") => origin[axis],
+            Some(offset) => {
+                let offset: i32 = offset.parse().map_err(|_| invalid())?;
+                origin[axis]
+                    .checked_add(offset)
+                    .ok_or_else(|| format!("out of range: {}", coordinate))?
+            }
+            None => coordinate.parse().map_err(|_| invalid())?,
+        };
+    }
+    Ok(IVec3::from_array(position))
+}
+
+/// Blocks are named like in Minecraft, e.g. `redstone_wall_torch[facing=north]`.
+fn block_type(block: &str) -> Result<Option<BlockType>, String> {
+    let state = BlockState::parse(block);
+    match to_block_type(&state) {
+        MappedState::Air => Ok(None),
+        MappedState::Block(block_type) => Ok(Some(block_type)),
+        MappedState::Unsupported => Err(format!("unsupported block {}", block)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_coordinates_add_to_the_origin() {
+        assert_eq!(
+            position(["~", "~2", "~-3"], IVec3::new(1, 1, 1)),
+            Ok(IVec3::new(1, 3, -2))
+        );
+    }
+
+    #[test]
+    fn rejects_relative_coordinates_out_of_range() {
+        assert!(position(["~2147483647", "0", "0"], IVec3::ONE).is_err());
+        assert!(position(["0", "~-2", "0"], IVec3::splat(i32::MIN)).is_err());
+    }
+
+    #[test]
+    fn stack_counts_stay_within_the_block_limit() {
+        assert_eq!(stack_count("3"), Ok(3));
+        assert_eq!(stack_count(&MAX_VOLUME.to_string()), Ok(MAX_VOLUME as u32));
+        assert!(stack_count("0").is_err());
+        assert!(stack_count(&(MAX_VOLUME + 1).to_string()).is_err());
+        assert!(stack_count("2147483648").is_err());
+    }
+}
@@ -0,0 +1,386 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        InputSystems,
+        keyboard::{Key, KeyboardInput},
+    },
+    math::I64Vec3,
+    prelude::*,
+};
+use std::collections::VecDeque;
+
+use crate::{
+    GameLoop,
+    bindings::{Action, KeyBindings},
+    blocks::{BlockType, NeighbourUpdate},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place, Remove},
+    history_plugin::History,
+    interactions::{HoveredBlockInfo, Selection, StackPreview, stack_changes, stack_volume},
+    redstone::GlobalTick,
+};
+
+mod commands;
+
+use commands::{Command, StackOffset, parse};
+
+/// Lines of output kept on screen.
+const MAX_OUTPUT: usize = 8;
+
+/// Most blocks a single `fill`, `clone` or `stack` touches, same limit as Minecraft.
+const MAX_VOLUME: i64 = 32768;
+
+#[derive(Resource, Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    output: VecDeque<String>,
+    submitted: Vec<String>,
+    last: Option<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn output(&self) -> impl Iterator<Item = &String> {
+        self.output.iter()
+    }
+
+    fn print(&mut self, line: String) {
+        self.output.push_back(line);
+        while self.output.len() > MAX_OUTPUT {
+            self.output.pop_front();
+        }
+    }
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_systems(PreUpdate, capture_console_input.after(InputSystems))
+            .add_systems(Update, run_console_commands.in_set(GameLoop::Input));
+    }
+}
+
+/// Typing goes to the console while it is open, keys are hidden from every other
+/// system until it closes.
+fn capture_console_input(
+    mut console: ResMut<Console>,
+    bindings: Res<KeyBindings>,
+    mut keyboard: MessageReader<KeyboardInput>,
+    mut key_input: ResMut<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+) {
+    if !console.open {
+        keyboard.clear();
+        if bindings.just_pressed(Action::OpenConsole, &key_input, &mouse_buttons) {
+            console.open = true;
+            key_input.reset_all();
+        }
+        return;
+    }
+
+    for key in keyboard.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+
+        match &key.logical_key {
+            Key::Escape => console.open = false,
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                if !line.trim().is_empty() {
+                    console.submitted.push(line.clone());
+                    console.last = Some(line);
+                }
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::ArrowUp => {
+                if let Some(last) = console.last.clone() {
+                    console.input = last;
+                }
+            }
+            _ => {
+                if let Some(text) = &key.text {
+                    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                    console.input.push_str(&text);
+                }
+            }
+        }
+    }
+
+    if console.open {
+        key_input.reset_all();
+    }
+}
+
+fn place(position: IVec3, block_type: BlockType) -> BlockChange {
+    BlockChange::Place(Place::new(
+        Some(block_type),
+        position,
+        true,
+        None,
+        NeighbourUpdate::EXTENDED.to_vec(),
+    ))
+}
+
+fn remove(position: IVec3) -> BlockChange {
+    BlockChange::Remove(Remove::new(
+        position,
+        true,
+        None,
+        NeighbourUpdate::EXTENDED.to_vec(),
+    ))
+}
+
+/// Every position in the box spanned by two corners, `None` when it is too large.
+fn positions(from: IVec3, to: IVec3) -> Option<Vec<IVec3>> {
+    let min = from.min(to);
+    let max = from.max(to);
+    // Corners can be a whole `i32` apart on every axis, so even the `i64` volume can
+    // overflow
+    let size = max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE;
+    let volume = size.x.checked_mul(size.y)?.checked_mul(size.z)?;
+    if volume > MAX_VOLUME {
+        return None;
+    }
+
+    let mut positions = Vec::new();
+    for y in min.y..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                positions.push(IVec3::new(x, y, z));
+            }
+        }
+    }
+    Some(positions)
+}
+
+/// How far `clone` moves blocks, `None` when the copy would not fit in the world.
+fn clone_offset(from: IVec3, to: IVec3, destination: IVec3) -> Option<IVec3> {
+    let offset = destination.checked_sub(from.min(to))?;
+    from.max(to).checked_add(offset).map(|_| offset)
+}
+
+/// Checked when previewing and again when applying, the selection may have changed in
+/// between.
+fn check_stack(context: &CommandContext, count: u32, offset: IVec3) -> Result<(), String> {
+    match stack_volume(&context.grid, &context.selection, count, offset) {
+        Some(blocks) if blocks as i64 > MAX_VOLUME => {
+            Err(format!("more than {} blocks", MAX_VOLUME))
+        }
+        Some(_) => Ok(()),
+        None => Err("the last copy lands past the edge of the world".to_string()),
+    }
+}
+
+/// What commands read from the world around them.
+#[derive(SystemParam)]
+struct CommandContext<'w> {
+    grid: Res<'w, Grid>,
+    history: Res<'w, History>,
+    hovered: Res<'w, HoveredBlockInfo>,
+    selection: Res<'w, Selection>,
+}
+
+fn run_console_commands(
+    mut console: ResMut<Console>,
+    context: CommandContext,
+    mut stack: ResMut<StackPreview>,
+    mut queue: ResMut<BlockChangeQueue>,
+    mut tick_counter: ResMut<GlobalTick>,
+    mut time: ResMut<Time<Fixed>>,
+) {
+    let submitted = std::mem::take(&mut console.submitted);
+    for line in submitted {
+        console.print(format!("> {}", line));
+
+        let origin = context.hovered.position.unwrap_or_default();
+        let command = match parse(&line, origin) {
+            Ok(command) => command,
+            Err(err) => {
+                console.print(format!("! {}", err));
+                continue;
+            }
+        };
+
+        let keeps_world = matches!(command, Command::TickRate(_) | Command::StackCancel);
+        if context.history.is_rewound() && !keeps_world {
+            console.print("! rewound, resume with (Tab) first".to_string());
+            continue;
+        }
+
+        let result = match command {
+            Command::SetBlock { position, block } => {
+                match block {
+                    Some(block_type) => queue.push_input(place(position, block_type)),
+                    None => queue.push_input(remove(position)),
+                }
+                Ok(format!("set block at {}", position))
+            }
+            Command::Fill { from, to, block } => match positions(from, to) {
+                Some(positions) => {
+                    let count = positions.len();
+                    for position in positions {
+                        match block {
+                            Some(block_type) => queue.push_input(place(position, block_type)),
+                            None if context.grid.get(position).is_some() => {
+                                queue.push_input(remove(position))
+                            }
+                            None => {}
+                        }
+                    }
+                    Ok(format!("filled {} blocks", count))
+                }
+                None => Err(format!("more than {} blocks", MAX_VOLUME)),
+            },
+            Command::Clone {
+                from,
+                to,
+                destination,
+            } => match (positions(from, to), clone_offset(from, to, destination)) {
+                (Some(positions), Some(offset)) => {
+                    // Read everything first, the destination may overlap the source
+                    let blocks: Vec<(IVec3, Option<BlockType>)> = positions
+                        .into_iter()
+                        .map(|position| {
+                            let block_type = context
+                                .grid
+                                .get(position)
+                                .map(|data| data.block_type)
+                                .filter(|block_type| *block_type != BlockType::Air);
+                            (position + offset, block_type)
+                        })
+                        .collect();
+
+                    let count = blocks.len();
+                    for (position, block_type) in blocks {
+                        match block_type {
+                            Some(block_type) => queue.push_input(place(position, block_type)),
+                            None if context.grid.get(position).is_some() => {
+                                queue.push_input(remove(position))
+                            }
+                            None => {}
+                        }
+                    }
+                    Ok(format!("cloned {} blocks", count))
+                }
+                (None, _) => Err(format!("more than {} blocks", MAX_VOLUME)),
+                (_, None) => Err(format!("destination {} out of range", destination)),
+            },
+            Command::Stack { count, offset } => match context.selection.bounds() {
+                Some((min, max)) => {
+                    let offset = match offset {
+                        StackOffset::Direction(direction) => max
+                            .checked_sub(min)
+                            .and_then(|size| size.checked_add(IVec3::ONE))
+                            .and_then(|size| direction.checked_mul(size)),
+                        StackOffset::Vector(offset) => Some(offset),
+                    };
+                    offset
+                        .ok_or_else(|| "the selection is too large to stack".to_string())
+                        .and_then(|offset| check_stack(&context, count, offset).map(|()| offset))
+                        .map(|offset| {
+                            stack.set(count, offset);
+                            format!(
+                                "previewing {} copies {} apart, stack apply to build",
+                                count, offset
+                            )
+                        })
+                }
+                None => Err("select a region with (B) first".to_string()),
+            },
+            Command::StackApply => match stack.take() {
+                Some((count, offset)) => check_stack(&context, count, offset).map(|()| {
+                    let changes = stack_changes(&context.grid, &context.selection, count, offset);
+                    let placed = changes.len();
+                    queue.push_input(BlockChange::Batch(changes));
+                    format!("stacked {} blocks", placed)
+                }),
+                None => Err("nothing to apply, preview with stack N first".to_string()),
+            },
+            Command::StackCancel => {
+                stack.clear();
+                Ok("stack cancelled".to_string())
+            }
+            Command::TickStep(ticks) => {
+                if tick_counter.is_running() {
+                    Err("pause with (Tab) before stepping".to_string())
+                } else {
+                    tick_counter.step(ticks);
+                    Ok(format!("stepping {} ticks", ticks))
+                }
+            }
+            Command::TickRate(None) => Ok(format!(
+                "{:.1} ticks per second",
+                1.0 / time.timestep().as_secs_f64()
+            )),
+            Command::TickRate(Some(rate)) => {
+                time.set_timestep_hz(rate);
+                Ok(format!("tick rate set to {:.1}", rate))
+            }
+        };
+
+        match result {
+            Ok(message) => console.print(message),
+            Err(err) => console.print(format!("! {}", err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_rejects_corners_a_whole_i32_apart() {
+        assert!(
+            positions(
+                IVec3::new(-2_000_000_000, 0, 0),
+                IVec3::new(2_000_000_000, 0, 0)
+            )
+            .is_none()
+        );
+        assert_eq!(
+            positions(IVec3::ZERO, IVec3::new(1, 1, 0)).map(|p| p.len()),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn positions_rejects_boxes_spread_on_every_axis() {
+        assert!(positions(IVec3::MIN, IVec3::MAX).is_none());
+        assert!(
+            positions(
+                IVec3::splat(-2_000_000_000),
+                IVec3::new(2_000_000_000, 1, 2_000_000_000)
+            )
+            .is_none()
+        );
+        assert_eq!(
+            positions(IVec3::splat(-1), IVec3::ONE).map(|p| p.len()),
+            Some(27)
+        );
+    }
+
+    #[test]
+    fn clone_offset_keeps_the_copy_in_range() {
+        assert_eq!(
+            clone_offset(IVec3::ZERO, IVec3::ONE, IVec3::splat(5)),
+            Some(IVec3::splat(5))
+        );
+        assert_eq!(
+            clone_offset(IVec3::ZERO, IVec3::ONE, IVec3::splat(i32::MAX)),
+            None
+        );
+    }
+}
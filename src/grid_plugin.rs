@@ -1,11 +1,21 @@
-use bevy::prelude::*;
-use std::collections::HashMap;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_prng::WyRand;
+use bevy_rand::prelude::GlobalRng;
+use rand_core::RngCore;
+use std::{collections::VecDeque, fmt};
 
 use crate::{
     BlockData, BlockType,
     block_position::BlockPos,
     blocks::{ALL_DIRS, Block, NeighbourUpdate, RecomputedResult, Tickable},
-    redstone::{GlobalTick, NotifyDelay, Scheduler, Tick},
+    chunk::{Chunk, ChunkMap},
+    redstone::{
+        GlobalTick, NotifyDelay, Scheduler, Tick,
+        islands::{CircuitIslands, run_tile_ticks_in_parallel},
+        rules::{SimulationRules, shuffle},
+        ticks::TickPhase,
+    },
     render::{DirtyBlocks, DirtyRender},
 };
 
@@ -14,48 +24,175 @@ pub enum BlockChange {
     Place(Place),
     Remove(Remove),
     NotifyNeighbours(BlockPos),
+    /// Applied as one transaction, every neighbour is notified once after all changes
+    /// are in place instead of after each of them. Batches do not nest.
+    Batch(Vec<BlockChange>),
 }
 
 #[derive(Resource, Default)]
 pub struct BlockChangeQueue {
-    changes: Vec<BlockChange>,
+    block_events: Vec<BlockChange>,
+    player_input: Vec<BlockChange>,
 }
 
 impl BlockChangeQueue {
     pub fn push(&mut self, change: BlockChange) {
-        self.changes.push(change);
+        self.block_events.push(change);
     }
-    pub fn drain(&mut self) -> vec::Drain<'_, BlockChange> {
-        self.changes.drain(..)
+
+    pub fn push_input(&mut self, change: BlockChange) {
+        self.player_input.push(change);
+    }
+
+    pub fn has_player_input(&self) -> bool {
+        !self.player_input.is_empty()
+    }
+
+    /// Block events waiting for the next apply phase.
+    pub fn block_events(&self) -> &[BlockChange] {
+        &self.block_events
+    }
+
+    pub fn replace_block_events(&mut self, changes: Vec<BlockChange>) {
+        self.block_events = changes;
+    }
+
+    /// Drops every change still waiting, block events and player input alike.
+    pub fn clear(&mut self) {
+        self.block_events.clear();
+        self.player_input.clear();
+    }
+
+    /// Changes waiting for the given phase, tile ticks are kept by the `Scheduler`.
+    pub fn take(&mut self, phase: TickPhase) -> Vec<BlockChange> {
+        match phase {
+            TickPhase::TileTicks => Vec::new(),
+            TickPhase::BlockEvents => std::mem::take(&mut self.block_events),
+            TickPhase::PlayerInput => std::mem::take(&mut self.player_input),
+        }
     }
 }
 
 pub struct GridPlugin;
 
+/// A single mutation of the grid together with the block it replaced, `None` is empty.
+#[derive(Debug, Clone)]
+pub struct GridMutation {
+    pub position: IVec3,
+    pub before: Option<BlockType>,
+    pub after: Option<BlockType>,
+}
+
 #[derive(Resource, Default)]
 pub struct Grid {
-    blocks: HashMap<IVec3, BlockData>,
+    blocks: ChunkMap,
+    rules: SimulationRules,
+    revision: u64,
+    layout_revision: u64,
+    journal: Option<Vec<GridMutation>>,
 }
 
 impl Grid {
-    pub fn get(&self, pos: IVec3) -> Option<&BlockData> {
-        self.blocks.get(&pos)
+    pub fn rules(&self) -> &SimulationRules {
+        &self.rules
     }
 
-    pub fn get_blocktype(&self, pos: IVec3) -> Option<&BlockType> {
-        self.blocks.get(&pos).map(|b| &b.block_type)
+    pub fn rules_mut(&mut self) -> &mut SimulationRules {
+        &mut self.rules
     }
 
-    pub fn get_mut(&mut self, pos: IVec3) -> Option<&mut BlockData> {
-        self.blocks.get_mut(&pos)
+    pub fn get(&self, pos: IVec3) -> Option<&BlockData> {
+        self.blocks.get(pos)
+    }
+
+    pub fn get_blocktype(&self, pos: IVec3) -> Option<&BlockType> {
+        self.blocks.get(pos).map(|b| &b.block_type)
     }
 
     pub fn insert(&mut self, pos: IVec3, data: BlockData) {
+        let kind = std::mem::discriminant(&data.block_type);
+        if self.get_blocktype(pos).map(std::mem::discriminant) != Some(kind) {
+            self.layout_revision += 1;
+        }
+        self.journal(pos, Some(data.block_type));
         self.blocks.insert(pos, data);
+        self.revision += 1;
     }
 
     pub fn remove(&mut self, pos: IVec3) {
-        self.blocks.remove(&pos);
+        if self.get(pos).is_some() {
+            self.layout_revision += 1;
+        }
+        self.journal(pos, None);
+        self.blocks.remove(pos);
+        self.revision += 1;
+    }
+
+    fn journal(&mut self, position: IVec3, after: Option<BlockType>) {
+        let before = self.get_blocktype(position).copied();
+        if let Some(journal) = &mut self.journal {
+            journal.push(GridMutation {
+                position,
+                before,
+                after,
+            });
+        }
+    }
+
+    /// Puts `block_type` at `pos`, or clears it for `None`.
+    pub fn set(&mut self, pos: IVec3, block_type: Option<BlockType>) {
+        match block_type {
+            Some(block_type) => self.insert(pos, BlockData { block_type }),
+            None => self.remove(pos),
+        }
+    }
+
+    /// Starts recording every mutation, in order, until the journal is taken.
+    pub fn record_mutations(&mut self) {
+        self.journal.get_or_insert_default();
+    }
+
+    /// Mutations recorded since they were last taken.
+    pub fn mutations(&self) -> &[GridMutation] {
+        self.journal.as_deref().unwrap_or_default()
+    }
+
+    /// Mutations recorded since the last call, recording carries on afterwards.
+    pub fn take_mutations(&mut self) -> Vec<GridMutation> {
+        self.journal
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Bumped on every mutation, lets caches tell whether the grid changed under them.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Revision of the chunk at `coords`, `None` while it is empty.
+    pub fn chunk_revision(&self, coords: IVec3) -> Option<u64> {
+        self.blocks.revision(coords)
+    }
+
+    /// Only bumped when a block appears, disappears or turns into another kind of block,
+    /// state changes such as dust power leave it alone.
+    pub fn layout_revision(&self) -> u64 {
+        self.layout_revision
+    }
+
+    /// Every non-empty chunk, keyed by chunk coordinates.
+    pub fn chunks(&self) -> impl Iterator<Item = (&IVec3, &Chunk)> {
+        self.blocks.chunks()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        self.chunks().flat_map(|(_, chunk)| chunk.iter())
+    }
+
+    /// Blocks inside the inclusive box spanned by `min` and `max`.
+    pub fn range(&self, min: IVec3, max: IVec3) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        self.blocks.range(min, max)
     }
 
     pub fn is_powered(&self, pos: IVec3) -> bool {
@@ -82,6 +219,42 @@ impl Grid {
 
     pub fn get_direct_signal(&self, pos: IVec3) -> u8 {
         info!("Asking for signal on position: {}", pos);
+        let Some(asking_block) = self.get_blocktype(pos) else {
+            return 0;
+        };
+        let new_power = self.signal_into(pos, asking_block);
+        info!("Power found: {}", new_power);
+        new_power
+    }
+
+    /// Power a component at `pos` responds to. Quasi-connected components are also
+    /// powered by anything that would power the space above them, but since they only
+    /// re-evaluate when they receive a neighbour update, a change above them alone
+    /// leaves them in a BUD state until something else updates them.
+    pub fn is_activated(&self, pos: IVec3, activation: Activation) -> bool {
+        if self.get_direct_signal(pos) > 0 {
+            return true;
+        }
+
+        match activation {
+            Activation::Direct => false,
+            Activation::QuasiConnected => self.is_quasi_powered(pos),
+        }
+    }
+
+    /// Off in Bedrock and when the rule is turned off, see
+    /// [`SimulationRules::quasi_connectivity`].
+    pub fn is_quasi_powered(&self, pos: IVec3) -> bool {
+        if !self.rules.quasi_connectivity() {
+            return false;
+        }
+
+        let above = pos + IVec3::Y;
+        let asking_block = self.get_blocktype(above).unwrap_or(&BlockType::Air);
+        self.signal_into(above, asking_block) > 0
+    }
+
+    fn signal_into(&self, pos: IVec3, asking_block: &BlockType) -> u8 {
         let mut new_power = 0;
         for dir in ALL_DIRS {
             let neighbour_pos = pos + dir;
@@ -89,10 +262,6 @@ impl Grid {
                 continue;
             };
 
-            let Some(asking_block) = self.get_blocktype(pos) else {
-                continue;
-            };
-
             new_power = new_power.max(neighbour_block.strong_power_emitted_to(
                 pos,
                 neighbour_pos,
@@ -102,11 +271,19 @@ impl Grid {
             new_power =
                 new_power.max(neighbour_block.weak_power_emitted(pos, neighbour_pos, asking_block));
         }
-        info!("Power found: {}", new_power);
         new_power
     }
 }
 
+/// How a component reads power from its surroundings.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Activation {
+    #[default]
+    Direct,
+    /// Pistons, droppers and dispensers, see [`Grid::is_activated`].
+    QuasiConnected,
+}
+
 #[derive(Event, Clone, Debug)]
 pub struct Place {
     position: IVec3,
@@ -161,52 +338,434 @@ impl Remove {
 impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Grid>()
-            .init_resource::<BlockChangeQueue>();
+            .init_resource::<BlockChangeQueue>()
+            .init_resource::<UpdateLoopGuard>()
+            .init_resource::<UpdateTrace>()
+            .init_resource::<CircuitIslands>();
     }
 }
 
 pub fn queue_block_change(event: On<BlockChange>, mut queue: ResMut<BlockChangeQueue>) {
     info!("Queueing up event: {:?}", event.event());
-    queue.push(event.event().clone());
+    queue.push_input(event.event().clone());
+}
+
+/// Maximum number of immediate updates processed in a single tick.
+const UPDATE_BUDGET: usize = 10_000;
+/// Updates of a single position within one tick before it is considered oscillating.
+const OSCILLATION_THRESHOLD: u32 = 64;
+
+#[derive(Debug, Clone)]
+pub struct UpdateLoop {
+    pub tick: Tick,
+    pub positions: Vec<IVec3>,
+}
+
+/// Keeps positions that keep re-scheduling each other from freezing the game.
+#[derive(Resource, Default)]
+pub struct UpdateLoopGuard {
+    budget: usize,
+    tick: Tick,
+    visits: HashMap<IVec3, u32>,
+    detected: Option<UpdateLoop>,
+}
+
+impl UpdateLoopGuard {
+    pub fn detected(&self) -> Option<&UpdateLoop> {
+        self.detected.as_ref()
+    }
+
+    pub(crate) fn with_budget(budget: usize) -> Self {
+        Self {
+            budget,
+            ..default()
+        }
+    }
+
+    pub(crate) fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub(crate) fn spend(&mut self, updates: usize) {
+        self.budget = self.budget.saturating_sub(updates);
+    }
+
+    fn begin_tick(&mut self) {
+        self.budget = UPDATE_BUDGET;
+    }
+
+    fn resume(&mut self) {
+        self.detected = None;
+        self.visits.clear();
+    }
+
+    /// Returns false once the position has been updated too often within the same tick.
+    fn visit(&mut self, position: IVec3, now: Tick) -> bool {
+        if self.tick != now {
+            self.tick = now;
+            self.visits.clear();
+        }
+
+        let visits = self.visits.entry(position).or_default();
+        *visits += 1;
+        if *visits < OSCILLATION_THRESHOLD {
+            return true;
+        }
+
+        let mut positions: Vec<_> = self
+            .visits
+            .iter()
+            .filter(|(_, visits)| **visits >= OSCILLATION_THRESHOLD / 2)
+            .map(|(position, _)| *position)
+            .collect();
+        positions.sort_by_key(|p| (p.x, p.y, p.z));
+
+        warn!("Update loop detected at tick {}: {:?}", now, positions);
+        self.detected = Some(UpdateLoop {
+            tick: now,
+            positions,
+        });
+        false
+    }
+
+    /// Called whenever the immediate queue runs dry, nothing can be oscillating then.
+    fn settle(&mut self) {
+        self.visits.clear();
+    }
+}
+
+/// Ticks kept in the update trace.
+const TRACED_TICKS: usize = 20;
+
+/// Where an update was picked up from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateSource {
+    /// Popped off `scheduler.immediate`.
+    Immediate,
+    /// Drained from `DirtyBlocks` after a neighbour changed.
+    Neighbour,
+}
+
+/// A `RecomputedResult` that outlives the block it came from, the neighbour updates
+/// are only counted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateOutcome {
+    Changed {
+        new_block: Option<BlockType>,
+        visual_update: bool,
+        self_tick: Option<NotifyDelay>,
+        neighbours: usize,
+    },
+    Unchanged,
+}
+
+impl From<&RecomputedResult<'_>> for UpdateOutcome {
+    fn from(result: &RecomputedResult<'_>) -> Self {
+        match result {
+            RecomputedResult::Changed {
+                new_block,
+                visual_update,
+                self_tick,
+                neighbor_tick,
+            } => UpdateOutcome::Changed {
+                new_block: *new_block,
+                visual_update: *visual_update,
+                self_tick: self_tick.clone(),
+                neighbours: neighbor_tick.len(),
+            },
+            RecomputedResult::Unchanged => UpdateOutcome::Unchanged,
+        }
+    }
+}
+
+/// A single block update, in the order it ran.
+#[derive(Debug, Clone)]
+pub struct TracedUpdate {
+    pub position: IVec3,
+    pub source: UpdateSource,
+    pub outcome: UpdateOutcome,
+}
+
+impl TracedUpdate {
+    pub fn new(position: IVec3, source: UpdateSource, result: &RecomputedResult<'_>) -> Self {
+        Self {
+            position,
+            source,
+            outcome: result.into(),
+        }
+    }
+}
+
+impl fmt::Display for TracedUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = self.position;
+        let source = match self.source {
+            UpdateSource::Immediate => "immediate",
+            UpdateSource::Neighbour => "neighbour",
+        };
+        write!(f, "(x: {}, y: {}, z: {}) {}: ", p.x, p.y, p.z, source)?;
+
+        match &self.outcome {
+            UpdateOutcome::Changed {
+                new_block,
+                visual_update,
+                self_tick,
+                neighbours,
+            } => {
+                match new_block {
+                    Some(block_type) => write!(f, "changed to {:?}", block_type)?,
+                    None => write!(f, "removed")?,
+                }
+                if let Some(self_tick) = self_tick {
+                    write!(f, ", self tick {:?}", self_tick)?;
+                }
+                if *neighbours > 0 {
+                    write!(f, ", {} neighbours", neighbours)?;
+                }
+                if !visual_update {
+                    write!(f, ", not redrawn")?;
+                }
+                Ok(())
+            }
+            UpdateOutcome::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/// Every update of a single tick, in the order they ran.
+#[derive(Debug, Clone)]
+pub struct TickTrace {
+    pub tick: Tick,
+    pub updates: Vec<TracedUpdate>,
+}
+
+/// Updates of the last few ticks that did any work, oldest first.
+#[derive(Resource, Default)]
+pub struct UpdateTrace {
+    ticks: VecDeque<TickTrace>,
+}
+
+impl UpdateTrace {
+    pub fn ticks(&self) -> impl Iterator<Item = &TickTrace> {
+        self.ticks.iter()
+    }
+
+    pub fn latest(&self) -> Option<&TickTrace> {
+        self.ticks.back()
+    }
+
+    pub(crate) fn record(&mut self, now: Tick, update: TracedUpdate) {
+        match self.ticks.back_mut() {
+            Some(trace) if trace.tick == now => trace.updates.push(update),
+            _ => {
+                self.ticks.push_back(TickTrace {
+                    tick: now,
+                    updates: vec![update],
+                });
+                if self.ticks.len() > TRACED_TICKS {
+                    self.ticks.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Every update recorded so far, whatever tick it belonged to.
+    pub(crate) fn take(&mut self) -> Vec<TracedUpdate> {
+        self.ticks
+            .drain(..)
+            .flat_map(|trace| trace.updates)
+            .collect()
+    }
+
+    /// Appends updates that ran elsewhere, in the order they ran.
+    pub(crate) fn extend(&mut self, now: Tick, updates: Vec<TracedUpdate>) {
+        for update in updates {
+            self.record(now, update);
+        }
+    }
+}
+
+/// Bookkeeping for a run of updates: the loop guard, what gets recalculated and
+/// redrawn afterwards, and the order the updates ran in.
+pub(crate) struct UpdatePass<'a> {
+    pub guard: &'a mut UpdateLoopGuard,
+    pub dirty_blocks: &'a mut DirtyBlocks,
+    pub dirty_render: &'a mut DirtyRender,
+    pub trace: &'a mut UpdateTrace,
+}
+
+/// The resources behind an [`UpdatePass`].
+#[derive(SystemParam)]
+pub struct UpdateBookkeeping<'w> {
+    guard: ResMut<'w, UpdateLoopGuard>,
+    dirty_blocks: ResMut<'w, DirtyBlocks>,
+    dirty_render: ResMut<'w, DirtyRender>,
+    trace: ResMut<'w, UpdateTrace>,
+}
+
+impl UpdateBookkeeping<'_> {
+    fn pass(&mut self) -> UpdatePass<'_> {
+        UpdatePass {
+            guard: &mut self.guard,
+            dirty_blocks: &mut self.dirty_blocks,
+            dirty_render: &mut self.dirty_render,
+            trace: &mut self.trace,
+        }
+    }
 }
 
 pub fn grid_apply_changes(
     mut queue: ResMut<BlockChangeQueue>,
     mut grid: ResMut<Grid>,
-    mut dirty_blocks: ResMut<DirtyBlocks>,
-    mut dirty_render: ResMut<DirtyRender>,
     mut scheduler: ResMut<Scheduler>,
-    global_tick: Res<GlobalTick>,
+    mut global_tick: ResMut<GlobalTick>,
+    mut islands: ResMut<CircuitIslands>,
+    mut bookkeeping: UpdateBookkeeping,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
 ) {
+    let mut pass = bookkeeping.pass();
+
+    // While halted on a loop only the player gets to change the world, so the loop
+    // can be taken apart before the simulation is resumed
+    let halted = pass.guard.detected().is_some() && !global_tick.is_running();
+    if pass.guard.detected().is_some() && !halted {
+        info!("Resuming after update loop");
+        pass.guard.resume();
+    }
+
     let now = global_tick.read();
+    let rng = &mut **rng;
+    pass.guard.begin_tick();
+
+    // Leftovers from a tick that ran out of budget go first
+    if !process_immediate(&mut grid, &mut scheduler, &mut pass, now, rng) {
+        halt_on_loop(pass.guard, &mut global_tick);
+        return;
+    }
 
-    // TODO: Queue VecDec?
-    let changes: Vec<_> = queue.drain().collect();
-    for change in changes {
-        if let Some(position) = apply_change(&mut grid, &change, &mut dirty_blocks, &mut queue) {
-            info!("Current block proccessed: {}", position);
-            schedule_self_tick(position, &mut scheduler, now, &change);
+    let phases: &[TickPhase] = if halted {
+        &[TickPhase::PlayerInput]
+    } else {
+        &TickPhase::ALL
+    };
 
-            schedule_ticks_and_mark_neighbours(
-                position,
-                &grid,
-                &mut scheduler,
-                &mut dirty_blocks,
-                &change,
-                now,
-            );
+    for phase in phases.iter().copied() {
+        match phase {
+            TickPhase::TileTicks => {
+                // Islands can't reach each other, so their tile ticks can run side by
+                // side. Falls back to the loop below when that would not give the exact
+                // same result.
+                if !grid.rules().randomized_update_order() {
+                    islands.refresh(&grid);
+                    run_tile_ticks_in_parallel(
+                        &mut islands,
+                        &mut grid,
+                        &mut scheduler,
+                        &mut pass,
+                        now,
+                    );
+                }
+
+                // Each due tile tick runs to completion, including the updates it
+                // causes, before the next one starts.
+                while let Some(position) = scheduler.next_tile_tick() {
+                    scheduler.immediate.push_back(position);
+                    if !process_immediate(&mut grid, &mut scheduler, &mut pass, now, rng) {
+                        halt_on_loop(pass.guard, &mut global_tick);
+                        return;
+                    }
+                }
+            }
+            TickPhase::BlockEvents | TickPhase::PlayerInput => {
+                for change in queue.take(phase) {
+                    if let BlockChange::Batch(batch) = &change {
+                        let notifications = apply_batch(
+                            batch,
+                            &mut grid,
+                            &mut scheduler,
+                            pass.dirty_blocks,
+                            pass.dirty_render,
+                            &mut queue,
+                            now,
+                        );
+                        notify_neighbours(
+                            notifications,
+                            &grid,
+                            &mut scheduler,
+                            pass.dirty_blocks,
+                            now,
+                            rng,
+                        );
+                        continue;
+                    }
+
+                    if let Some(position) =
+                        apply_change(&mut grid, &change, pass.dirty_blocks, &mut queue)
+                    {
+                        info!("Current block proccessed: {}", position);
+                        schedule_self_tick(position, &mut scheduler, now, &change);
+
+                        schedule_ticks_and_mark_neighbours(
+                            position,
+                            &grid,
+                            &mut scheduler,
+                            pass.dirty_blocks,
+                            &change,
+                            now,
+                            rng,
+                        );
+
+                        mark_for_redraw(position, pass.dirty_render, &change);
+                    }
+                }
 
-            mark_for_redraw(position, &mut dirty_render, &change);
+                if !process_immediate(&mut grid, &mut scheduler, &mut pass, now, rng) {
+                    halt_on_loop(pass.guard, &mut global_tick);
+                    return;
+                }
+            }
         }
     }
+}
+
+fn halt_on_loop(guard: &UpdateLoopGuard, global_tick: &mut GlobalTick) {
+    if guard.detected().is_some() {
+        global_tick.stop();
+    }
+}
 
+/// Returns false if the queue could not be drained, either because the tick ran out
+/// of budget or because an update loop was detected.
+pub(crate) fn process_immediate(
+    grid: &mut Grid,
+    scheduler: &mut Scheduler,
+    pass: &mut UpdatePass,
+    now: Tick,
+    rng: &mut impl RngCore,
+) -> bool {
     while let Some(position) = scheduler.immediate.pop_front() {
+        if pass.guard.budget == 0 {
+            warn!("Update budget exhausted, continuing next tick");
+            scheduler.immediate.push_front(position);
+            return false;
+        }
+        pass.guard.budget -= 1;
+
+        if !pass.guard.visit(position, now) {
+            scheduler.immediate.clear();
+            return false;
+        }
+
         let block_type = match grid.get_blocktype(position) {
             Some(bt) => *bt,
             None => continue,
         };
 
-        let result = block_type.on_tick(&grid, position);
+        let result = block_type.on_tick(grid, position);
+        pass.trace.record(
+            now,
+            TracedUpdate::new(position, UpdateSource::Immediate, &result),
+        );
 
         if let RecomputedResult::Changed {
             new_block,
@@ -224,6 +783,11 @@ pub fn grid_apply_changes(
                 scheduler.schedule(position, &self_tick, now);
             }
 
+            let mut neighbor_tick = neighbor_tick.to_vec();
+            if grid.rules().randomized_update_order() {
+                shuffle(&mut neighbor_tick, rng);
+            }
+
             for update in neighbor_tick {
                 let position = position + update.position;
                 if grid.get(position).is_some() {
@@ -231,15 +795,18 @@ pub fn grid_apply_changes(
                     scheduler.schedule(position, &update.notification, now);
 
                     info!("Marking block as dirty: {}", position);
-                    dirty_blocks.mark(position);
+                    pass.dirty_blocks.mark(position);
                 }
             }
 
             if visual_update {
-                dirty_render.mark(position);
+                pass.dirty_render.mark(position);
             }
         }
     }
+
+    pass.guard.settle();
+    true
 }
 
 fn try_place(grid: &mut Grid, event: &Place) -> Option<IVec3> {
@@ -257,7 +824,7 @@ fn try_remove(grid: &mut Grid, position: &BlockPos, queue: &mut BlockChangeQueue
     info!("Triggering on remove for position: {:?}", position);
     block_type.on_remove(grid, position, queue);
 
-    if grid.get_mut(position.value()).is_some() {
+    if grid.get(position.value()).is_some() {
         grid.remove(position.value());
         return Some(position.value());
     }
@@ -279,6 +846,50 @@ fn apply_change(
             }
             None
         }
+        BlockChange::Batch(_) => None,
+    }
+}
+
+/// Applies a batch without notifying anything and returns the neighbour updates it
+/// causes, each position once.
+fn apply_batch(
+    batch: &[BlockChange],
+    grid: &mut Grid,
+    scheduler: &mut Scheduler,
+    dirty_blocks: &mut DirtyBlocks,
+    dirty_render: &mut DirtyRender,
+    queue: &mut BlockChangeQueue,
+    now: Tick,
+) -> Vec<NeighbourUpdate> {
+    let mut notified = HashSet::new();
+    let mut notifications = Vec::new();
+
+    for change in batch {
+        let Some(position) = apply_change(grid, change, dirty_blocks, queue) else {
+            continue;
+        };
+        schedule_self_tick(position, scheduler, now, change);
+        mark_for_redraw(position, dirty_render, change);
+
+        for n_update in neighbour_updates(change) {
+            let position = position + n_update.position;
+            if notified.insert(position) {
+                notifications.push(NeighbourUpdate::new(
+                    position,
+                    n_update.notification.clone(),
+                ));
+            }
+        }
+    }
+
+    notifications
+}
+
+fn neighbour_updates(change: &BlockChange) -> &[NeighbourUpdate] {
+    match change {
+        BlockChange::Place(event) => &event.neighbor_tick,
+        BlockChange::Remove(event) => &event.neighbor_tick,
+        _ => &[],
     }
 }
 
@@ -300,15 +911,33 @@ fn schedule_ticks_and_mark_neighbours(
     dirty_blocks: &mut DirtyBlocks,
     change: &BlockChange,
     now: Tick,
+    rng: &mut impl RngCore,
 ) {
-    let neighbor_tick = match change {
-        BlockChange::Place(event) => &event.neighbor_tick,
-        BlockChange::Remove(event) => &event.neighbor_tick,
-        _ => return,
-    };
+    let neighbor_tick = neighbour_updates(change)
+        .iter()
+        .map(|n_update| {
+            NeighbourUpdate::new(position + n_update.position, n_update.notification.clone())
+        })
+        .collect();
+
+    notify_neighbours(neighbor_tick, grid, scheduler, dirty_blocks, now, rng);
+}
+
+/// Schedules and marks the given positions, `position` of each update is absolute.
+fn notify_neighbours(
+    mut neighbor_tick: Vec<NeighbourUpdate>,
+    grid: &Grid,
+    scheduler: &mut Scheduler,
+    dirty_blocks: &mut DirtyBlocks,
+    now: Tick,
+    rng: &mut impl RngCore,
+) {
+    if grid.rules().randomized_update_order() {
+        shuffle(&mut neighbor_tick, rng);
+    }
 
     for n_update in neighbor_tick {
-        let position = position + n_update.position;
+        let position = n_update.position;
         if grid.get(position).is_some() {
             info!("Scheduling neighbour: {}", position);
             scheduler.schedule(position, &n_update.notification, now);
@@ -330,3 +959,107 @@ fn mark_for_redraw(position: IVec3, dirty_render: &mut DirtyRender, change: &Blo
         dirty_render.mark(position);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::{Dirt, Dust, RedStone, RedStoneLamp, StandardGrass},
+        testing::TestWorld,
+    };
+
+    fn floor(world: &mut TestWorld, length: i32) {
+        for x in -1..=length {
+            world.place(
+                BlockType::StandardGrass(StandardGrass),
+                IVec3::new(x, -1, 0),
+                IVec3::Y,
+            );
+        }
+        world.tick();
+    }
+
+    /// Two lamps queued alternately, the way a pair of blocks re-scheduling each other
+    /// with `NotifyDelay::Immediate` fills the queue, far past the threshold.
+    fn oscillating_pair() -> (TestWorld, [IVec3; 2]) {
+        let mut world = TestWorld::new();
+        floor(&mut world, 2);
+        let pair = [IVec3::new(0, 0, 0), IVec3::new(1, 0, 0)];
+        for position in pair {
+            world.place(
+                BlockType::RedStoneLamp(RedStoneLamp::default()),
+                position,
+                IVec3::Y,
+            );
+        }
+        world.run(2);
+
+        let immediate = &mut world.scheduler_mut().immediate;
+        for _ in 0..OSCILLATION_THRESHOLD * 4 {
+            immediate.extend(pair);
+        }
+        (world, pair)
+    }
+
+    #[test]
+    fn oscillating_pair_halts_the_simulation() {
+        let (mut world, pair) = oscillating_pair();
+        world.tick();
+
+        let detected = world.loop_guard().detected().expect("loop detected");
+        assert_eq!(detected.positions, pair.to_vec());
+        assert!(!world.is_running());
+        assert!(world.scheduler().immediate.is_empty());
+
+        let halted_at = world.now();
+        world.run(5);
+        assert_eq!(world.now(), halted_at);
+    }
+
+    #[test]
+    fn player_input_is_applied_while_halted() {
+        let (mut world, _) = oscillating_pair();
+        world.tick();
+        assert!(world.loop_guard().detected().is_some());
+
+        world.place(BlockType::Dirt(Dirt), IVec3::new(2, 0, 0), IVec3::Y);
+        world.tick();
+        assert_eq!(
+            world.grid().get_blocktype(IVec3::new(2, 0, 0)),
+            Some(&BlockType::Dirt(Dirt))
+        );
+        assert!(!world.is_running());
+    }
+
+    #[test]
+    fn long_dust_line_is_not_a_loop() {
+        let length = 15;
+        let mut world = TestWorld::new();
+        floor(&mut world, length);
+        for x in 1..=length {
+            world.place(
+                BlockType::Dust(Dust::default()),
+                IVec3::new(x, 0, 0),
+                IVec3::Y,
+            );
+        }
+        world.run(2);
+
+        world.place(BlockType::RedStone(RedStone), IVec3::ZERO, IVec3::Y);
+        world.run(5);
+
+        assert!(world.loop_guard().detected().is_none());
+        assert!(world.is_running());
+        for x in 1..=length {
+            assert_eq!(
+                world
+                    .grid()
+                    .get_blocktype(IVec3::new(x, 0, 0))
+                    .map(|block| block.power()),
+                Some(16 - x as u8),
+                "dust at {}",
+                x
+            );
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::{collections::VecDeque, sync::Arc};
+
+use crate::{
+    GameLoop,
+    bindings::{Action, Actions},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, GridMutation},
+    redstone::{GlobalTick, Scheduler, Tick},
+    render::{DirtyBlocks, DirtyRender},
+};
+
+/// Ticks of history kept before the oldest are dropped.
+const MAX_RECORDED_TICKS: usize = 6000;
+/// Ticks skipped per key press while holding shift.
+const FAST_STEP: usize = 10;
+
+/// What happened during one tick. The grid is kept as the mutations applied to it, the
+/// scheduler and pending block events as they were when the tick ended. Ticks that
+/// left the scheduler alone share the snapshot of the tick before.
+struct TickRecord {
+    tick: Tick,
+    mutations: Vec<GridMutation>,
+    scheduler: Arc<Scheduler>,
+    block_events: Vec<BlockChange>,
+}
+
+#[derive(Resource, Default)]
+pub struct History {
+    records: VecDeque<TickRecord>,
+    /// Record the world is currently rewound to, `None` while live.
+    cursor: Option<usize>,
+}
+
+impl History {
+    pub fn is_rewound(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    pub fn recorded_ticks(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Index of the record the world currently reflects.
+    pub fn position(&self) -> usize {
+        self.cursor.unwrap_or(self.records.len().saturating_sub(1))
+    }
+
+    pub fn tick_at(&self, index: usize) -> Option<Tick> {
+        self.records.get(index).map(|record| record.tick)
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_systems(Startup, start_recording)
+            .add_systems(Update, scrub_history.in_set(GameLoop::Input))
+            .add_systems(FixedUpdate, record_history.in_set(GameLoop::Cleanup));
+    }
+}
+
+/// Run condition keeping the simulation frozen while looking at the past.
+pub fn is_live(history: Res<History>) -> bool {
+    !history.is_rewound()
+}
+
+fn start_recording(mut grid: ResMut<Grid>) {
+    grid.record_mutations();
+}
+
+fn record_history(
+    mut history: ResMut<History>,
+    mut grid: ResMut<Grid>,
+    scheduler: Res<Scheduler>,
+    mut queue: ResMut<BlockChangeQueue>,
+    tick_counter: Res<GlobalTick>,
+) {
+    if let Some(cursor) = history.cursor {
+        if !tick_counter.is_running() {
+            return;
+        }
+
+        // Resuming from the past discards the future
+        info!(
+            "Resuming from tick {}, discarding later history",
+            tick_counter.read()
+        );
+        history.records.truncate(cursor + 1);
+        history.cursor = None;
+        if let Some(record) = history.records.back() {
+            queue.replace_block_events(record.block_events.clone());
+        }
+    }
+
+    let mutations = grid.take_mutations();
+    let now = tick_counter.read();
+
+    // Comparing is far cheaper than copying the scheduler, and most ticks leave it as
+    // it was
+    let snapshot = match history.records.back() {
+        Some(record) if *record.scheduler == *scheduler => record.scheduler.clone(),
+        _ => Arc::new(scheduler.clone()),
+    };
+
+    if let Some(record) = history.records.back_mut()
+        && record.tick == now
+    {
+        if mutations.is_empty() {
+            return;
+        }
+        record.mutations.extend(mutations);
+        record.scheduler = snapshot;
+        record.block_events = queue.block_events().to_vec();
+        return;
+    }
+
+    history.records.push_back(TickRecord {
+        tick: now,
+        mutations,
+        scheduler: snapshot,
+        block_events: queue.block_events().to_vec(),
+    });
+
+    if history.records.len() > MAX_RECORDED_TICKS {
+        history.records.pop_front();
+    }
+}
+
+/// Everything rewinding puts back the way it was.
+#[derive(SystemParam)]
+struct RewindableWorld<'w> {
+    grid: ResMut<'w, Grid>,
+    scheduler: ResMut<'w, Scheduler>,
+    tick_counter: ResMut<'w, GlobalTick>,
+    queue: ResMut<'w, BlockChangeQueue>,
+}
+
+fn scrub_history(
+    key_input: Res<ButtonInput<KeyCode>>,
+    actions: Actions,
+    mut history: ResMut<History>,
+    mut world: RewindableWorld,
+    mut dirty_blocks: ResMut<DirtyBlocks>,
+    mut dirty_render: ResMut<DirtyRender>,
+) {
+    let step = if key_input.pressed(KeyCode::ShiftLeft) {
+        FAST_STEP
+    } else {
+        1
+    };
+
+    let current = history.position();
+    let target = if actions.just_pressed(Action::Rewind) {
+        current.saturating_sub(step)
+    } else if actions.just_pressed(Action::Step) && history.is_rewound() {
+        (current + step).min(history.recorded_ticks().saturating_sub(1))
+    } else {
+        return;
+    };
+
+    if history.records.is_empty() || target == current && history.is_rewound() {
+        return;
+    }
+
+    if !history.is_rewound() {
+        // Anything not yet recorded belongs to the last tick
+        let mutations = world.grid.take_mutations();
+        if let Some(record) = history.records.back_mut() {
+            record.mutations.extend(mutations);
+            record.block_events = world.queue.block_events().to_vec();
+        }
+        world.tick_counter.stop();
+    }
+
+    // Undo newer ticks newest first, redo older ones oldest first
+    for index in (target + 1..=current).rev() {
+        for mutation in history.records[index].mutations.iter().rev() {
+            world.grid.set(mutation.position, mutation.before);
+            dirty_render.mark(mutation.position);
+        }
+    }
+    for index in current + 1..=target {
+        for mutation in &history.records[index].mutations {
+            world.grid.set(mutation.position, mutation.after);
+            dirty_render.mark(mutation.position);
+        }
+    }
+    // Scrubbing is not part of the history
+    world.grid.take_mutations();
+
+    let record = &history.records[target];
+    *world.scheduler = Scheduler::clone(&record.scheduler);
+    world.tick_counter.set(record.tick);
+    dirty_blocks.positions.clear();
+    world.queue.replace_block_events(Vec::new());
+
+    info!("Rewound to tick {}", record.tick);
+    history.cursor = Some(target);
+}
@@ -0,0 +1,137 @@
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*};
+use std::mem::discriminant;
+
+use crate::{
+    SelectedBlock,
+    bindings::{Action, Actions},
+    blocks::{BlockType, Dropper, Dust, RedStone, RedStoneLamp, RedStoneTorch, StandardGrass},
+    grid_plugin::Grid,
+    interactions::HoveredBlockInfo,
+};
+
+pub const SLOTS: usize = 9;
+
+#[derive(Resource)]
+pub struct Hotbar {
+    slots: [Option<BlockType>; SLOTS],
+    selected: Option<usize>,
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        let mut slots = [None; SLOTS];
+        slots[0] = Some(BlockType::StandardGrass(StandardGrass));
+        slots[1] = Some(BlockType::RedStone(RedStone));
+        slots[2] = Some(BlockType::RedStoneLamp(RedStoneLamp::default()));
+        slots[3] = Some(BlockType::Dust(Dust::default()));
+        slots[4] = Some(BlockType::RedStoneTorch(RedStoneTorch::default()));
+        slots[5] = Some(BlockType::Dropper(Dropper::default()));
+        Self {
+            slots,
+            selected: None,
+        }
+    }
+}
+
+impl Hotbar {
+    pub fn slots(&self) -> &[Option<BlockType>; SLOTS] {
+        &self.slots
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    fn block(&self) -> Option<BlockType> {
+        self.selected.and_then(|slot| self.slots[slot])
+    }
+
+    /// Selecting the selected slot again deselects it.
+    fn toggle(&mut self, slot: usize) {
+        if self.selected == Some(slot) {
+            self.selected = None;
+        } else {
+            self.selected = Some(slot);
+        }
+    }
+
+    fn cycle(&mut self, steps: i32) {
+        let slot = match self.selected {
+            Some(slot) => (slot as i32 + steps).rem_euclid(SLOTS as i32) as usize,
+            None => 0,
+        };
+        self.selected = Some(slot);
+    }
+
+    /// Replaces the slot already holding this kind of block, otherwise the selected
+    /// slot, and selects it.
+    fn pick(&mut self, block_type: BlockType) {
+        let slot = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_some_and(|b| discriminant(&b) == discriminant(&block_type)))
+            .or(self.selected)
+            .or_else(|| self.slots.iter().position(Option::is_none))
+            .unwrap_or(0);
+        self.slots[slot] = Some(block_type);
+        self.selected = Some(slot);
+    }
+}
+
+pub(crate) fn select_block(
+    actions: Actions,
+    mut hotbar: ResMut<Hotbar>,
+    mut selected_block: ResMut<SelectedBlock>,
+) {
+    let Some(slot) = (0..SLOTS).find(|slot| actions.just_pressed(Action::SelectSlot(*slot as u8)))
+    else {
+        return;
+    };
+
+    hotbar.toggle(slot);
+    selected_block.0 = hotbar.block();
+    info!("Selected block: {:?}", selected_block.0);
+}
+
+/// The wheel zooms while Ctrl is held and cycles the hotbar otherwise.
+pub(crate) fn scroll_hotbar(
+    key_input: Res<ButtonInput<KeyCode>>,
+    mouse_wheel_input: Res<AccumulatedMouseScroll>,
+    mut hotbar: ResMut<Hotbar>,
+    mut selected_block: ResMut<SelectedBlock>,
+) {
+    if mouse_wheel_input.delta.y == 0.0
+        || key_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+
+    hotbar.cycle(-mouse_wheel_input.delta.y.signum() as i32);
+    selected_block.0 = hotbar.block();
+}
+
+/// Copies the hovered block with its state.
+pub(crate) fn pick_block(
+    actions: Actions,
+    hovered_block: Res<HoveredBlockInfo>,
+    grid: Res<Grid>,
+    mut hotbar: ResMut<Hotbar>,
+    mut selected_block: ResMut<SelectedBlock>,
+) {
+    if !actions.just_pressed(Action::PickBlock) {
+        return;
+    }
+
+    let Some(block_type) = hovered_block
+        .position
+        .and_then(|position| grid.get(position))
+        .map(|data| data.block_type)
+        .filter(|block_type| *block_type != BlockType::Air)
+    else {
+        return;
+    };
+
+    hotbar.pick(block_type);
+    selected_block.0 = Some(block_type);
+    info!("Picked {:?}", block_type);
+}
@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    bindings::{Action, Actions},
+    blocks::{BlockType, NeighbourUpdate, Placement, Tickable},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place},
+    interactions::HoveredBlockInfo,
+    redstone::junctions::JunctionType,
+};
+
+/// A piece of block state the inspector shows and edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Shape,
+    Power,
+    Lit,
+    AttachedFace,
+}
+
+impl Field {
+    pub fn of(block_type: &BlockType) -> &'static [Field] {
+        match block_type {
+            BlockType::Dust(_) => &[Field::Shape, Field::Power],
+            BlockType::RedStoneLamp(_) => &[Field::Power],
+            BlockType::RedStoneTorch(_) => &[Field::Lit, Field::AttachedFace],
+            _ => &[],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Shape => "shape",
+            Field::Power => "power",
+            Field::Lit => "lit",
+            Field::AttachedFace => "attached_face",
+        }
+    }
+
+    pub fn value(&self, block_type: &BlockType) -> String {
+        match (self, block_type) {
+            (Field::Shape, BlockType::Dust(dust)) => format!("{:?}", dust.shape),
+            (Field::Power, block_type) => block_type.power().to_string(),
+            (Field::Lit, BlockType::RedStoneTorch(torch)) => torch.lit.to_string(),
+            (Field::AttachedFace, BlockType::RedStoneTorch(torch)) => {
+                face_name(torch.attached_face).to_string()
+            }
+            _ => "-".to_string(),
+        }
+    }
+
+    /// The block with this field stepped forwards or backwards, numbers stay within
+    /// redstone power and choices wrap around.
+    fn edited(&self, block_type: &BlockType, step: i32) -> BlockType {
+        match (self, block_type) {
+            (Field::Shape, BlockType::Dust(dust)) => {
+                let mut dust = *dust;
+                dust.shape = cycle(&JunctionType::ALL, dust.shape, step);
+                BlockType::Dust(dust)
+            }
+            (Field::Power, BlockType::Dust(dust)) => {
+                BlockType::Dust(dust.with_power(stepped_power(dust.power(), step)))
+            }
+            (Field::Power, BlockType::RedStoneLamp(lamp)) => {
+                BlockType::RedStoneLamp(lamp.with_power(stepped_power(lamp.power(), step)))
+            }
+            (Field::Lit, BlockType::RedStoneTorch(torch)) => {
+                let mut torch = *torch;
+                torch.lit = !torch.lit;
+                BlockType::RedStoneTorch(torch)
+            }
+            (Field::AttachedFace, BlockType::RedStoneTorch(torch)) => {
+                let mut torch = *torch;
+                torch.attached_face =
+                    cycle(Placement::ClickedFace.facings(), torch.attached_face, step);
+                BlockType::RedStoneTorch(torch)
+            }
+            (_, block_type) => *block_type,
+        }
+    }
+}
+
+fn stepped_power(power: u8, step: i32) -> u8 {
+    (power as i32 + step).clamp(0, 15) as u8
+}
+
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: i32) -> T {
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+fn face_name(face: IVec3) -> &'static str {
+    match face {
+        IVec3::Y => "up",
+        IVec3::NEG_Y => "down",
+        IVec3::NEG_Z => "north",
+        IVec3::Z => "south",
+        IVec3::X => "east",
+        IVec3::NEG_X => "west",
+        _ => "?",
+    }
+}
+
+pub fn block_name(block_type: &BlockType) -> &'static str {
+    match block_type {
+        BlockType::Air => "Air",
+        BlockType::StandardGrass(_) => "StandardGrass",
+        BlockType::Dirt(_) => "Dirt",
+        BlockType::RedStone(_) => "RedStone",
+        BlockType::RedStoneLamp(_) => "RedStoneLamp",
+        BlockType::RedStoneTorch(_) => "RedStoneTorch",
+        BlockType::Dust(_) => "Dust",
+        BlockType::Dropper(_) => "Dropper",
+    }
+}
+
+/// Shows the hovered block, or the pinned one, with the field picked for editing.
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pinned: Option<IVec3>,
+    field: usize,
+}
+
+impl Inspector {
+    pub fn target(&self, hovered: &HoveredBlockInfo) -> Option<IVec3> {
+        self.pinned.or(hovered.position)
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned.is_some()
+    }
+
+    pub fn field(&self) -> usize {
+        self.field
+    }
+}
+
+/// Picks fields with up and down and steps their value with left and right, every
+/// edit is placed like any other block so the neighbours get notified.
+pub(crate) fn inspect_block(
+    actions: Actions,
+    hovered_block: Res<HoveredBlockInfo>,
+    grid: Res<Grid>,
+    mut inspector: ResMut<Inspector>,
+    mut queue: ResMut<BlockChangeQueue>,
+) {
+    if actions.just_pressed(Action::InspectBlock) {
+        inspector.pinned = match inspector.pinned {
+            Some(_) => None,
+            None => hovered_block.position,
+        };
+    }
+
+    let Some(position) = inspector.target(&hovered_block) else {
+        return;
+    };
+    let Some(block_type) = grid.get(position).map(|data| data.block_type) else {
+        return;
+    };
+    let fields = Field::of(&block_type);
+    if fields.is_empty() {
+        return;
+    }
+
+    let count = fields.len();
+    let mut index = inspector.field % count;
+    if actions.just_pressed(Action::PreviousField) {
+        index = (index + count - 1) % count;
+    }
+    if actions.just_pressed(Action::NextField) {
+        index = (index + 1) % count;
+    }
+    inspector.field = index;
+
+    let step = if actions.just_pressed(Action::IncreaseField) {
+        1
+    } else if actions.just_pressed(Action::DecreaseField) {
+        -1
+    } else {
+        return;
+    };
+
+    let field = fields[index];
+    let edited = field.edited(&block_type, step);
+    if edited == block_type {
+        return;
+    }
+
+    queue.push_input(BlockChange::Place(Place::new(
+        Some(edited),
+        position,
+        true,
+        None,
+        NeighbourUpdate::EXTENDED.to_vec(),
+    )));
+    info!(
+        "Set {} of {} to {}",
+        field.name(),
+        position,
+        field.value(&edited)
+    );
+}
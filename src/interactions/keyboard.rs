@@ -1,85 +1,64 @@
 use bevy::prelude::*;
 
 use crate::{
-    SelectedBlock,
-    blocks::{BlockType, Dust, RedStone, RedStoneLamp, RedStoneTorch, StandardGrass},
-    redstone::GlobalTick,
+    bindings::{Action, Actions},
+    grid_plugin::Grid,
+    redstone::{GlobalTick, graph::CompiledBackend},
+    render::{PowerLabels, SignalFlow},
 };
 
-pub(crate) fn select_block(
-    key_input: Res<ButtonInput<KeyCode>>,
-    mut selected_block: ResMut<SelectedBlock>,
-    mut tick_counter: ResMut<GlobalTick>,
-) {
-    if key_input.just_pressed(KeyCode::Digit1) {
-        if let Some(BlockType::StandardGrass(StandardGrass { .. })) = selected_block.0 {
-            info!("Deselecting Grass");
-            selected_block.0 = None;
+pub(crate) fn toggle_simulation(actions: Actions, mut tick_counter: ResMut<GlobalTick>) {
+    if actions.just_pressed(Action::ToggleRun) {
+        if tick_counter.is_running() {
+            tick_counter.stop();
         } else {
-            info!("Selecting Grass");
-            selected_block.0 = Some(BlockType::StandardGrass(StandardGrass::default()));
+            tick_counter.start();
         }
     }
+}
 
-    if key_input.just_pressed(KeyCode::Digit2) {
-        if let Some(BlockType::RedStone(RedStone { .. })) = selected_block.0 {
-            info!("Deselecting RedStone");
-            selected_block.0 = None;
-        } else {
-            info!("Selecting RedStone");
-            selected_block.0 = Some(BlockType::RedStone(RedStone::default()));
-        }
+pub(crate) fn toggle_edition(actions: Actions, mut grid: ResMut<Grid>) {
+    if actions.just_pressed(Action::ToggleEdition) {
+        grid.rules_mut().toggle_edition();
+        info!("Simulation rules: {:?}", grid.rules().edition);
     }
 
-    if key_input.just_pressed(KeyCode::Digit3) {
-        if let Some(BlockType::RedStoneLamp { .. }) = selected_block.0 {
-            info!("Deselecting RedStoneLamp");
-            selected_block.0 = None;
-        } else {
-            info!("Selecting RedStoneLamp");
-            selected_block.0 = Some(BlockType::RedStoneLamp(RedStoneLamp::default()));
-        }
+    if actions.just_pressed(Action::ToggleQuasiConnectivity) {
+        let rules = grid.rules_mut();
+        rules.quasi_connectivity = !rules.quasi_connectivity;
+        info!("Quasi-connectivity: {}", rules.quasi_connectivity);
     }
+}
 
-    if key_input.just_pressed(KeyCode::Digit4) {
-        if let Some(BlockType::Dust { .. }) = selected_block.0 {
-            info!("Deselecting Dust");
-            selected_block.0 = None;
-        } else {
-            info!("Selecting Dust");
-            selected_block.0 = Some(BlockType::Dust(Dust::default()));
-        }
+pub(crate) fn toggle_backend(actions: Actions, mut backend: ResMut<CompiledBackend>) {
+    if actions.just_pressed(Action::ToggleCompiled) {
+        backend.toggle();
+        info!("Compiled backend: {}", backend.is_enabled());
     }
 
-    if key_input.just_pressed(KeyCode::Digit5) {
-        if let Some(BlockType::RedStoneTorch { .. }) = selected_block.0 {
-            info!("Deselecting RedStone Torch");
-            selected_block.0 = None;
-        } else {
-            info!("Selecting RedStoneTorch");
-            selected_block.0 = Some(BlockType::RedStoneTorch(RedStoneTorch::default()));
-        }
+    if actions.just_pressed(Action::Faster) {
+        backend.faster();
+        info!("Compiled ticks per step: {}", backend.ticks_per_step());
     }
 
-    // if key_input.just_pressed(KeyCode::Digit6) {
-    //     if let Some(BlockType::StoneButton { .. }) = selected_block.0 {
-    //         info!("Deselecting Stone Button");
-    //         selected_block.0 = None;
-    //     } else {
-    //         info!("Selecting Stone Button");
-    //         selected_block.0 = Some(BlockType::StoneButton {
-    //             ticks: 10,
-    //             pressed: false,
-    //             attached_face: IVec3::NEG_X,
-    //         });
-    //     }
-    // }
+    if actions.just_pressed(Action::Slower) {
+        backend.slower();
+        info!("Compiled ticks per step: {}", backend.ticks_per_step());
+    }
+}
 
-    if key_input.just_pressed(KeyCode::Tab) {
-        if tick_counter.is_running() {
-            tick_counter.stop();
-        } else {
-            tick_counter.start();
-        }
+pub(crate) fn toggle_overlays(
+    actions: Actions,
+    mut labels: ResMut<PowerLabels>,
+    mut flow: ResMut<SignalFlow>,
+) {
+    if actions.just_pressed(Action::TogglePowerLabels) {
+        labels.toggle();
+        info!("Power labels: {}", labels.is_enabled());
+    }
+
+    if actions.just_pressed(Action::ToggleSignalFlow) {
+        flow.toggle();
+        info!("Signal flow: {}", flow.is_enabled());
     }
 }
@@ -1,35 +1,105 @@
 use bevy::{color::palettes::tailwind::RED_500, picking::pointer::PointerInteraction, prelude::*};
 
+mod hotbar;
 mod hover;
+mod inspector;
 mod keyboard;
 mod mouse_click;
+mod rotate;
+mod selection;
+mod stack;
+mod undo;
 
+pub use hotbar::{Hotbar, SLOTS};
 pub use hover::HoveredBlockInfo;
 pub use hover::track_grid_cordinate;
 pub use hover::track_hovered_block;
 pub use hover::untrack_hovered_block;
+pub use inspector::{Field, Inspector, block_name};
+pub use rotate::PendingFacing;
+pub use selection::{Clipboard, Selection};
+pub use stack::{StackPreview, stack_changes, stack_volume};
+pub use undo::EditHistory;
+
+#[cfg(test)]
+pub(crate) use mouse_click::placement;
 
 use crate::GameLoop;
-use crate::interactions::keyboard::select_block;
+use crate::grid_plugin::grid_apply_changes;
+use crate::history_plugin::is_live;
+use crate::interactions::hotbar::{pick_block, scroll_hotbar, select_block};
+use crate::interactions::inspector::inspect_block;
+use crate::interactions::keyboard::toggle_backend;
+use crate::interactions::keyboard::toggle_edition;
+use crate::interactions::keyboard::toggle_overlays;
+use crate::interactions::keyboard::toggle_simulation;
+use crate::interactions::mouse_click::DragPlacement;
+use crate::interactions::mouse_click::draw_drag_preview;
 use crate::interactions::mouse_click::request_delete_hovered_block;
 use crate::interactions::mouse_click::request_place_selected_block;
 use crate::interactions::mouse_click::try_place_in_world;
+use crate::interactions::rotate::{rotate_hovered_block, rotate_pending, track_camera_facing};
+use crate::interactions::selection::{draw_selection, is_selecting, select_region, use_clipboard};
+use crate::interactions::stack::draw_stack_preview;
+use crate::interactions::undo::{begin_edit, track_edits, undo_redo};
+use crate::systems::recalculate_dirty_blocks;
 
 pub struct BlockInteractionPlugin;
 
 impl Plugin for BlockInteractionPlugin {
     fn build(&self, app: &mut bevy::app::App) {
         app.init_resource::<HoveredBlockInfo>()
+            .init_resource::<EditHistory>()
+            .init_resource::<Selection>()
+            .init_resource::<Clipboard>()
+            .init_resource::<StackPreview>()
+            .init_resource::<DragPlacement>()
+            .init_resource::<Hotbar>()
+            .init_resource::<PendingFacing>()
+            .init_resource::<Inspector>()
             .add_systems(
                 Update,
                 (
                     draw_on_hover_arrow,
+                    draw_selection,
+                    draw_stack_preview,
+                    draw_drag_preview,
                     select_block,
-                    request_place_selected_block,
-                    request_delete_hovered_block,
+                    scroll_hotbar,
+                    pick_block,
+                    rotate_pending,
+                    track_camera_facing,
+                    toggle_simulation,
+                    toggle_edition,
+                    toggle_backend,
+                    toggle_overlays,
+                    select_region,
+                    (request_place_selected_block, request_delete_hovered_block)
+                        .run_if(not(is_selecting)),
                 )
                     .in_set(GameLoop::Input),
             )
+            .add_systems(
+                Update,
+                (use_clipboard, rotate_hovered_block, inspect_block)
+                    .in_set(GameLoop::Input)
+                    .run_if(is_live),
+            )
+            .add_systems(Update, undo_redo.in_set(GameLoop::Input).run_if(is_live))
+            .add_systems(
+                FixedUpdate,
+                begin_edit
+                    .in_set(GameLoop::Apply)
+                    .before(grid_apply_changes)
+                    .run_if(is_live),
+            )
+            .add_systems(
+                FixedUpdate,
+                track_edits
+                    .in_set(GameLoop::React)
+                    .after(recalculate_dirty_blocks)
+                    .run_if(is_live),
+            )
             .add_observer(try_place_in_world);
     }
 }
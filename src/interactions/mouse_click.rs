@@ -1,11 +1,11 @@
-use bevy::prelude::*;
+use bevy::{color::palettes::tailwind::SKY_400, ecs::system::SystemParam, prelude::*};
 
 use crate::{
     SelectedBlock,
     blocks::{Block, BlockType, NeighbourUpdate, RecomputedResult},
     grid_plugin::Remove,
     grid_plugin::{BlockChange, Grid, Place},
-    interactions::HoveredBlockInfo,
+    interactions::{HoveredBlockInfo, PendingFacing},
     render::DirtyRender,
 };
 
@@ -14,63 +14,195 @@ pub struct ClickEvent(pub Action);
 
 #[derive(Debug)]
 pub enum Action {
-    PlaceBlock(BlockType, IVec3, IVec3),
+    /// Block, clicked position, clicked face and the facing the block gets.
+    PlaceBlock(BlockType, IVec3, IVec3, IVec3),
     Interact(IVec3),
 }
 
+/// Most blocks a single drag places.
+const MAX_DRAG: i64 = 4096;
+
+/// A left drag in progress, blocks go on the plane of the face the drag started on.
+#[derive(Resource, Default)]
+pub struct DragPlacement {
+    start: Option<(IVec3, IVec3)>,
+    end: IVec3,
+    fill: bool,
+}
+
+impl DragPlacement {
+    /// Positions from the start to the end of the drag, a line along the longest axis
+    /// or the whole rectangle when filling. `None` when it is too large.
+    fn positions(&self) -> Option<Vec<IVec3>> {
+        let (start, _) = self.start?;
+        let delta = self.end - start;
+        let end = if self.fill {
+            self.end
+        } else {
+            let abs = delta.abs();
+            let axis = if abs.x >= abs.y && abs.x >= abs.z {
+                IVec3::X
+            } else if abs.y >= abs.z {
+                IVec3::Y
+            } else {
+                IVec3::Z
+            };
+            start + delta * axis
+        };
+
+        let min = start.min(end);
+        let max = start.max(end);
+        let size = (max - min + IVec3::ONE).as_i64vec3();
+        if size.x * size.y * size.z > MAX_DRAG {
+            return None;
+        }
+
+        let mut positions = Vec::new();
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                for x in min.x..=max.x {
+                    positions.push(IVec3::new(x, y, z));
+                }
+            }
+        }
+        Some(positions)
+    }
+}
+
+/// The block in hand and the facing it gets when placed.
+#[derive(SystemParam)]
+pub(crate) struct HeldBlock<'w> {
+    selected: Res<'w, SelectedBlock>,
+    pending: Res<'w, PendingFacing>,
+}
+
+/// A click places a single block, dragging places a line, or a rectangle while Shift
+/// is held, all in one batch when the button is released.
 pub(crate) fn request_place_selected_block(
     mut commands: Commands,
-    selected_block: Res<SelectedBlock>,
+    held: HeldBlock,
     mouse_buttons: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
     hovered_block: Res<HoveredBlockInfo>,
+    grid: Res<Grid>,
+    mut drag: ResMut<DragPlacement>,
 ) {
+    let hovered = hovered_block.position.zip(hovered_block.normal);
+
     if mouse_buttons.just_pressed(MouseButton::Left)
-        && let Some(position) = hovered_block.position
-        && let Some(normal) = hovered_block.normal
+        && let Some((position, normal)) = hovered
     {
-        if let Some(block_type) = selected_block.0 {
-            info!("Triggered placement!");
-            commands.trigger(ClickEvent(Action::PlaceBlock(block_type, position, normal)));
+        if held.selected.0.is_some() {
+            drag.start = Some((position + normal, normal));
+            drag.end = position + normal;
         } else {
             commands.trigger(ClickEvent(Action::Interact(position)));
         }
     }
+
+    let Some((start, normal)) = drag.start else {
+        return;
+    };
+
+    // Keep the end on the plane the drag started on
+    if let Some((position, hovered_normal)) = hovered {
+        let target = position + hovered_normal;
+        drag.end = target + normal.abs() * (start - target);
+    }
+    drag.fill = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if !mouse_buttons.just_released(MouseButton::Left) {
+        return;
+    }
+    let positions = drag.positions();
+    drag.start = None;
+    let Some(block_type) = held.selected.0 else {
+        return;
+    };
+
+    let facing = held.pending.resolve(&block_type, normal);
+    if drag.end == start {
+        info!("Triggered placement!");
+        commands.trigger(ClickEvent(Action::PlaceBlock(
+            block_type,
+            start - normal,
+            normal,
+            facing,
+        )));
+        return;
+    }
+
+    let Some(positions) = positions else {
+        warn!("Drag covers more than {} blocks", MAX_DRAG);
+        return;
+    };
+    let changes: Vec<BlockChange> = positions
+        .into_iter()
+        .filter(|position| {
+            grid.get(*position)
+                .is_none_or(|data| data.block_type == BlockType::Air)
+        })
+        .filter_map(|position| placement(&grid, block_type, position, facing))
+        .collect();
+    info!("Placing {} blocks", changes.len());
+    commands.trigger(BlockChange::Batch(changes));
 }
 
-pub(crate) fn try_place_in_world(event: On<ClickEvent>, mut commands: Commands, grid: Res<Grid>) {
-    if let Action::PlaceBlock(block_type, position, normal) = event.0 {
-        let position = position + normal;
-
-        // TODO: resolve side placement from normal
-
-        let result = block_type.on_placement(&grid, position, normal);
-
-        let (block_type, visual_update, self_tick, neighbor_tick) = match result {
-            RecomputedResult::Changed {
-                new_block,
-                visual_update,
-                self_tick,
-                neighbor_tick,
-            } => (
-                new_block.unwrap_or(block_type),
-                visual_update,
-                self_tick,
-                neighbor_tick,
-            ),
-            RecomputedResult::Unchanged => (block_type, false, None, NeighbourUpdate::NONE),
-        };
+pub(crate) fn draw_drag_preview(drag: Res<DragPlacement>, mut gizmos: Gizmos) {
+    let Some(positions) = drag.positions() else {
+        return;
+    };
+    for position in positions {
+        gizmos.cuboid(
+            Transform::from_translation(position.as_vec3()).with_scale(Vec3::splat(0.9)),
+            SKY_400,
+        );
+    }
+}
 
-        if !block_type.try_place(&grid, position) {
-            return;
-        };
+/// Resolves how the block ends up when placed at the position, `None` when it can't
+/// be placed there.
+pub(crate) fn placement(
+    grid: &Grid,
+    block_type: BlockType,
+    position: IVec3,
+    facing: IVec3,
+) -> Option<BlockChange> {
+    let result = block_type.on_placement(grid, position, facing);
 
-        commands.trigger(BlockChange::Place(Place::new(
-            Some(block_type),
-            position,
+    let (block_type, visual_update, self_tick, neighbor_tick) = match result {
+        RecomputedResult::Changed {
+            new_block,
             visual_update,
             self_tick,
-            neighbor_tick.to_vec(),
-        )));
+            neighbor_tick,
+        } => (
+            new_block.unwrap_or(block_type),
+            visual_update,
+            self_tick,
+            neighbor_tick,
+        ),
+        RecomputedResult::Unchanged => (block_type, false, None, NeighbourUpdate::NONE),
+    };
+
+    if !block_type.try_place(grid, position) {
+        return None;
+    };
+
+    Some(BlockChange::Place(Place::new(
+        Some(block_type),
+        position,
+        visual_update,
+        self_tick,
+        neighbor_tick.to_vec(),
+    )))
+}
+
+pub(crate) fn try_place_in_world(event: On<ClickEvent>, mut commands: Commands, grid: Res<Grid>) {
+    if let Action::PlaceBlock(block_type, position, normal, facing) = event.0
+        && let Some(change) = placement(&grid, block_type, position + normal, facing)
+    {
+        commands.trigger(change);
     }
 }
 
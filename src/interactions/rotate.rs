@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::{
+    SelectedBlock,
+    bindings::{Action, Actions},
+    blocks::{Block, BlockType, NeighbourUpdate, Placement},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place},
+    interactions::HoveredBlockInfo,
+    main_camera::InnerCamera,
+};
+
+/// Facing picked with the rotate key for the next placed block, cleared when another
+/// block is selected.
+#[derive(Resource)]
+pub struct PendingFacing {
+    facing: Option<IVec3>,
+    /// The horizontal direction the camera looks in.
+    looking: IVec3,
+}
+
+impl Default for PendingFacing {
+    fn default() -> Self {
+        Self {
+            facing: None,
+            looking: IVec3::NEG_Z,
+        }
+    }
+}
+
+impl PendingFacing {
+    /// The facing a new block gets: the pending one once turned, otherwise what the
+    /// placement rule of the block picks from the clicked face or the camera.
+    pub fn resolve(&self, block_type: &BlockType, normal: IVec3) -> IVec3 {
+        match block_type.placement() {
+            Placement::Fixed => normal,
+            Placement::ClickedFace => self.facing.unwrap_or(normal),
+            Placement::PlayerFacing => self.facing.unwrap_or(self.looking),
+        }
+    }
+}
+
+/// The horizontal direction closest to where a camera turned by `yaw` looks, cameras
+/// look down -Z before they are turned.
+fn facing_from_yaw(yaw: f32) -> IVec3 {
+    let forward = Vec2::new(-yaw.sin(), -yaw.cos());
+    if forward.x.abs() > forward.y.abs() {
+        IVec3::new(forward.x.signum() as i32, 0, 0)
+    } else {
+        IVec3::new(0, 0, forward.y.signum() as i32)
+    }
+}
+
+pub(crate) fn track_camera_facing(
+    camera: Single<&Transform, With<InnerCamera>>,
+    mut pending: ResMut<PendingFacing>,
+) {
+    let (yaw, _, _) = camera.rotation.to_euler(EulerRot::YXZ);
+    let looking = facing_from_yaw(yaw);
+    if pending.looking != looking {
+        pending.looking = looking;
+    }
+}
+
+/// The facing after `current` in `facings`, the first one when there is none yet.
+fn next_facing(facings: &[IVec3], current: Option<IVec3>) -> Option<IVec3> {
+    let next = current
+        .and_then(|current| facings.iter().position(|facing| *facing == current))
+        .map_or(0, |index| index + 1);
+    facings.get(next % facings.len().max(1)).copied()
+}
+
+pub(crate) fn rotate_pending(
+    actions: Actions,
+    selected_block: Res<SelectedBlock>,
+    mut pending: ResMut<PendingFacing>,
+) {
+    if selected_block.is_changed() {
+        pending.facing = None;
+    }
+
+    if !actions.just_pressed(Action::RotatePlacement) {
+        return;
+    }
+    let Some(block_type) = selected_block.0 else {
+        return;
+    };
+
+    pending.facing = next_facing(block_type.placement().facings(), pending.facing);
+    match pending.facing {
+        Some(facing) => info!("Placing facing {}", facing),
+        None => info!("{:?} has no facing", block_type),
+    }
+}
+
+/// Turns the hovered block to its next facing where it stands. Attached blocks skip
+/// faces with nothing to hang on.
+pub(crate) fn rotate_hovered_block(
+    actions: Actions,
+    hovered_block: Res<HoveredBlockInfo>,
+    grid: Res<Grid>,
+    mut queue: ResMut<BlockChangeQueue>,
+) {
+    if !actions.just_pressed(Action::RotateBlock) {
+        return;
+    }
+    let Some(position) = hovered_block.position else {
+        return;
+    };
+    let Some(block_type) = grid.get(position).map(|data| data.block_type) else {
+        return;
+    };
+
+    let placement = block_type.placement();
+    let facings: Vec<IVec3> = placement
+        .facings()
+        .iter()
+        .copied()
+        .filter(|facing| {
+            placement != Placement::ClickedFace || grid.get(position - *facing).is_some()
+        })
+        .collect();
+
+    let Some(turned) = next_facing(&facings, block_type.facing())
+        .and_then(|facing| block_type.turned(&grid, position, facing))
+    else {
+        return;
+    };
+    if turned == block_type {
+        return;
+    }
+
+    queue.push_input(BlockChange::Place(Place::new(
+        Some(turned),
+        position,
+        true,
+        None,
+        NeighbourUpdate::EXTENDED.to_vec(),
+    )));
+    info!("Turned {} to face {:?}", position, turned.facing());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{Dirt, Dropper, RedStoneTorch};
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn camera_yaw_snaps_to_the_closest_horizontal_facing() {
+        assert_eq!(facing_from_yaw(0.0), IVec3::NEG_Z);
+        assert_eq!(facing_from_yaw(FRAC_PI_2), IVec3::NEG_X);
+        assert_eq!(facing_from_yaw(PI), IVec3::Z);
+        assert_eq!(facing_from_yaw(-FRAC_PI_2), IVec3::X);
+        assert_eq!(facing_from_yaw(0.3), IVec3::NEG_Z);
+        assert_eq!(facing_from_yaw(-FRAC_PI_2 - 0.3), IVec3::X);
+    }
+
+    #[test]
+    fn facings_resolve_by_placement_rule() {
+        let mut pending = PendingFacing {
+            facing: None,
+            looking: IVec3::X,
+        };
+        let torch = BlockType::RedStoneTorch(RedStoneTorch::default());
+        assert_eq!(pending.resolve(&torch, IVec3::Z), IVec3::Z);
+        assert_eq!(pending.resolve(&BlockType::Dirt(Dirt), IVec3::Y), IVec3::Y);
+
+        let dropper = BlockType::Dropper(Dropper::default());
+        assert_eq!(pending.resolve(&dropper, IVec3::Y), IVec3::X);
+
+        pending.facing = Some(IVec3::NEG_X);
+        assert_eq!(pending.resolve(&torch, IVec3::Z), IVec3::NEG_X);
+        assert_eq!(pending.resolve(&dropper, IVec3::Y), IVec3::NEG_X);
+        assert_eq!(pending.resolve(&BlockType::Dirt(Dirt), IVec3::Y), IVec3::Y);
+
+        assert_eq!(
+            next_facing(Placement::PlayerFacing.facings(), Some(IVec3::NEG_X)),
+            Some(IVec3::NEG_Z)
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use bevy::{
+    color::palettes::tailwind::{SKY_400, YELLOW_400},
+    prelude::*,
+};
+
+use crate::{
+    bindings::{Action, Actions},
+    blocks::{BlockType, NeighbourUpdate},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place, Remove},
+    interactions::HoveredBlockInfo,
+    schematic::ExportRegion,
+};
+
+/// Two corners picked with the mouse while selection mode is on.
+#[derive(Resource, Default)]
+pub struct Selection {
+    active: bool,
+    first: Option<IVec3>,
+    second: Option<IVec3>,
+}
+
+impl Selection {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Minimum and maximum corner, both inclusive.
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let first = self.first?;
+        let second = self.second.unwrap_or(first);
+        Some((first.min(second), first.max(second)))
+    }
+}
+
+/// Copied blocks relative to the minimum corner of what was copied, air is left out.
+#[derive(Resource, Default)]
+pub struct Clipboard {
+    blocks: Vec<(IVec3, BlockType)>,
+    size: IVec3,
+}
+
+impl Clipboard {
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Turns the copied blocks and their directional state, keeping the minimum corner
+    /// at the origin.
+    fn transform(&mut self, transform: impl Fn(IVec3) -> IVec3) {
+        let corner = transform(self.size - IVec3::ONE);
+        let shift = corner.min(IVec3::ZERO);
+        for (offset, block_type) in &mut self.blocks {
+            *offset = transform(*offset) - shift;
+            *block_type = block_type.transformed(&transform);
+        }
+        self.size = corner.abs() + IVec3::ONE;
+    }
+}
+
+/// A quarter turn clockwise seen from above, north becomes east.
+fn rotate_clockwise(dir: IVec3) -> IVec3 {
+    IVec3::new(-dir.z, dir.y, dir.x)
+}
+
+/// Swaps east and west.
+fn mirror(dir: IVec3) -> IVec3 {
+    IVec3::new(-dir.x, dir.y, dir.z)
+}
+
+pub fn is_selecting(selection: Res<Selection>) -> bool {
+    selection.active
+}
+
+pub(crate) fn select_region(
+    actions: Actions,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    hovered_block: Res<HoveredBlockInfo>,
+    mut selection: ResMut<Selection>,
+    mut region: ResMut<ExportRegion>,
+) {
+    if actions.just_pressed(Action::SelectMode) {
+        selection.active = !selection.active;
+        info!("Selection mode: {}", selection.active);
+    }
+
+    if !selection.active {
+        return;
+    }
+
+    // The right button may also be bound to turning the camera
+    if mouse_buttons.just_pressed(MouseButton::Right) && !actions.just_pressed(Action::RotateCamera)
+    {
+        selection.first = None;
+        selection.second = None;
+        return;
+    }
+
+    if !mouse_buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(position) = hovered_block.position else {
+        return;
+    };
+
+    if selection.first.is_none() || selection.second.is_some() {
+        selection.first = Some(position);
+        selection.second = None;
+        return;
+    }
+
+    selection.second = Some(position);
+    if let Some((min, max)) = selection.bounds() {
+        region.set(min, max - min + IVec3::ONE);
+        info!("Selected {} to {}", min, max);
+    }
+}
+
+pub(crate) fn use_clipboard(
+    actions: Actions,
+    hovered_block: Res<HoveredBlockInfo>,
+    selection: Res<Selection>,
+    grid: Res<Grid>,
+    mut clipboard: ResMut<Clipboard>,
+    mut queue: ResMut<BlockChangeQueue>,
+) {
+    let copy = actions.just_pressed(Action::Copy);
+    let cut = actions.just_pressed(Action::Cut);
+    if (copy || cut)
+        && let Some((min, max)) = selection.bounds()
+    {
+        clipboard.blocks = grid
+            .range(min, max)
+            .filter(|(_, data)| data.block_type != BlockType::Air)
+            .map(|(position, data)| (position - min, data.block_type))
+            .collect();
+        clipboard.size = max - min + IVec3::ONE;
+        info!("Copied {} blocks", clipboard.len());
+
+        if cut {
+            for (offset, _) in &clipboard.blocks {
+                queue.push_input(BlockChange::Remove(Remove::new(
+                    min + *offset,
+                    true,
+                    None,
+                    NeighbourUpdate::EXTENDED.to_vec(),
+                )));
+            }
+        }
+    }
+
+    if actions.just_pressed(Action::RotateClipboard) {
+        clipboard.transform(rotate_clockwise);
+        info!("Rotated clipboard");
+    }
+
+    if actions.just_pressed(Action::MirrorClipboard) {
+        clipboard.transform(mirror);
+        info!("Mirrored clipboard");
+    }
+
+    if actions.just_pressed(Action::Paste)
+        && let Some(position) = hovered_block.position
+        && let Some(normal) = hovered_block.normal
+    {
+        let origin = position + normal;
+        for (offset, block_type) in &clipboard.blocks {
+            queue.push_input(BlockChange::Place(Place::new(
+                Some(*block_type),
+                origin + *offset,
+                true,
+                None,
+                NeighbourUpdate::EXTENDED.to_vec(),
+            )));
+        }
+        info!("Pasted {} blocks at {}", clipboard.len(), origin);
+    }
+}
+
+fn draw_box(gizmos: &mut Gizmos, min: IVec3, size: IVec3, color: impl Into<Color>) {
+    let center = min.as_vec3() + (size.as_vec3() - Vec3::ONE) / 2.0;
+    gizmos.cuboid(
+        Transform::from_translation(center).with_scale(size.as_vec3() + Vec3::splat(0.02)),
+        color,
+    );
+}
+
+/// The selected box, and where a paste would land while selecting.
+pub(crate) fn draw_selection(
+    selection: Res<Selection>,
+    clipboard: Res<Clipboard>,
+    hovered_block: Res<HoveredBlockInfo>,
+    mut gizmos: Gizmos,
+) {
+    if let Some((min, max)) = selection.bounds() {
+        draw_box(&mut gizmos, min, max - min + IVec3::ONE, YELLOW_400);
+    }
+
+    if selection.active
+        && !clipboard.is_empty()
+        && let Some(position) = hovered_block.position
+        && let Some(normal) = hovered_block.normal
+    {
+        draw_box(&mut gizmos, position + normal, clipboard.size, SKY_400);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::{Dirt, Dropper, Dust, RedStoneTorch},
+        redstone::junctions::JunctionType,
+    };
+
+    fn dust(shape: JunctionType) -> BlockType {
+        let mut dust = Dust::default().with_power(7);
+        dust.shape = shape;
+        BlockType::Dust(dust)
+    }
+
+    fn torch(attached_face: IVec3) -> BlockType {
+        BlockType::RedStoneTorch(RedStoneTorch {
+            lit: true,
+            attached_face,
+        })
+    }
+
+    /// A 3x2x2 copy with a corner and a T of dust, a wall torch and a dropper.
+    fn clipboard() -> Clipboard {
+        Clipboard {
+            blocks: vec![
+                (IVec3::new(0, 0, 0), BlockType::Dirt(Dirt)),
+                (IVec3::new(1, 0, 0), dust(JunctionType::CornerSE)),
+                (IVec3::new(2, 0, 1), dust(JunctionType::TNorth)),
+                (IVec3::new(0, 1, 1), torch(IVec3::NEG_Z)),
+                (
+                    IVec3::new(2, 1, 0),
+                    BlockType::Dropper(Dropper {
+                        facing: IVec3::X,
+                        triggered: false,
+                    }),
+                ),
+            ],
+            size: IVec3::new(3, 2, 2),
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_change_nothing() {
+        let original = clipboard();
+        let mut turned = clipboard();
+        for _ in 0..4 {
+            turned.transform(rotate_clockwise);
+            for (offset, _) in &turned.blocks {
+                assert!(offset.cmpge(IVec3::ZERO).all() && offset.cmplt(turned.size).all());
+            }
+        }
+        assert_eq!(turned.blocks, original.blocks);
+        assert_eq!(turned.size, original.size);
+    }
+
+    #[test]
+    fn mirroring_twice_changes_nothing() {
+        let original = clipboard();
+        let mut mirrored = clipboard();
+        mirrored.transform(mirror);
+        mirrored.transform(mirror);
+        assert_eq!(mirrored.blocks, original.blocks);
+        assert_eq!(mirrored.size, original.size);
+    }
+
+    #[test]
+    fn turning_moves_blocks_and_their_facing() {
+        let mut turned = clipboard();
+        turned.transform(rotate_clockwise);
+        assert_eq!(turned.size, IVec3::new(2, 2, 3));
+        assert_eq!(
+            turned.blocks,
+            [
+                (IVec3::new(1, 0, 0), BlockType::Dirt(Dirt)),
+                // North and east become east and south
+                (IVec3::new(1, 0, 1), dust(JunctionType::CornerNE)),
+                // North, east and west become east, south and north
+                (IVec3::new(0, 0, 2), dust(JunctionType::TEast)),
+                (IVec3::new(0, 1, 0), torch(IVec3::X)),
+                (
+                    IVec3::new(1, 1, 2),
+                    BlockType::Dropper(Dropper {
+                        facing: IVec3::Z,
+                        triggered: false,
+                    }),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mirroring_swaps_east_and_west() {
+        let mut mirrored = clipboard();
+        mirrored.transform(mirror);
+        assert_eq!(mirrored.size, IVec3::new(3, 2, 2));
+        assert_eq!(
+            mirrored.blocks,
+            [
+                (IVec3::new(2, 0, 0), BlockType::Dirt(Dirt)),
+                // North and east become north and west
+                (IVec3::new(1, 0, 0), dust(JunctionType::CornerSW)),
+                // A T open to the south stays one
+                (IVec3::new(0, 0, 1), dust(JunctionType::TNorth)),
+                (IVec3::new(2, 1, 1), torch(IVec3::NEG_Z)),
+                (
+                    IVec3::new(0, 1, 0),
+                    BlockType::Dropper(Dropper {
+                        facing: IVec3::NEG_X,
+                        triggered: false,
+                    }),
+                ),
+            ]
+        );
+
+        let mut single = Clipboard {
+            blocks: vec![(IVec3::ZERO, dust(JunctionType::TEast))],
+            size: IVec3::ONE,
+        };
+        single.transform(mirror);
+        // North, south and east become north, south and west
+        assert_eq!(single.blocks, [(IVec3::ZERO, dust(JunctionType::TWest))]);
+    }
+}
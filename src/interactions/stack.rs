@@ -0,0 +1,185 @@
+use bevy::{color::palettes::tailwind::SKY_400, prelude::*};
+
+use crate::{
+    blocks::{BlockType, NeighbourUpdate},
+    grid_plugin::{BlockChange, Grid, Place},
+    interactions::Selection,
+};
+
+/// Ghost blocks drawn at most, larger stacks only show the outline of each copy.
+const MAX_GHOSTS: usize = 4096;
+
+/// A stack of the selection waiting to be applied, shown as ghosts until then.
+#[derive(Resource, Default)]
+pub struct StackPreview {
+    pending: Option<(u32, IVec3)>,
+}
+
+impl StackPreview {
+    pub fn set(&mut self, count: u32, offset: IVec3) {
+        self.pending = Some((count, offset));
+    }
+
+    pub fn clear(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn take(&mut self) -> Option<(u32, IVec3)> {
+        self.pending.take()
+    }
+}
+
+fn selected_blocks(grid: &Grid, min: IVec3, max: IVec3) -> Vec<(IVec3, BlockType)> {
+    grid.range(min, max)
+        .filter(|(_, data)| data.block_type != BlockType::Air)
+        .map(|(position, data)| (position, data.block_type))
+        .collect()
+}
+
+/// The selected blocks the preview last drew ghosts for. Ghosts only care where blocks
+/// are, so they are read again when the selection or the layout of the grid changes.
+#[derive(Default)]
+pub(crate) struct PreviewedBlocks {
+    key: Option<(u64, IVec3, IVec3)>,
+    positions: Vec<IVec3>,
+}
+
+impl PreviewedBlocks {
+    fn get(&mut self, grid: &Grid, min: IVec3, max: IVec3) -> &[IVec3] {
+        let key = (grid.layout_revision(), min, max);
+        if self.key != Some(key) {
+            self.key = Some(key);
+            self.positions = selected_blocks(grid, min, max)
+                .into_iter()
+                .map(|(position, _)| position)
+                .collect();
+        }
+        &self.positions
+    }
+}
+
+/// How far copy number `copy` is moved from the selection, `None` past the edge of
+/// the world.
+fn shift(offset: IVec3, copy: u32) -> Option<IVec3> {
+    offset.checked_mul(IVec3::splat(i32::try_from(copy).ok()?))
+}
+
+/// Blocks a stack of `count` copies places, `None` when there is nothing selected or
+/// the last copy lands past the edge of the world. Copies in between lie between the
+/// selection and the last one, so they always fit when it does.
+pub fn stack_volume(
+    grid: &Grid,
+    selection: &Selection,
+    count: u32,
+    offset: IVec3,
+) -> Option<usize> {
+    let (min, max) = selection.bounds()?;
+    let last = shift(offset, count)?;
+    min.checked_add(last)?;
+    max.checked_add(last)?;
+    Some(
+        selected_blocks(grid, min, max)
+            .len()
+            .saturating_mul(count as usize),
+    )
+}
+
+/// Every copy of the selected blocks as one batch, so the neighbours are recomputed
+/// once for the whole stack. Check the stack with [`stack_volume`] first, copies past
+/// the edge of the world are left out.
+pub fn stack_changes(
+    grid: &Grid,
+    selection: &Selection,
+    count: u32,
+    offset: IVec3,
+) -> Vec<BlockChange> {
+    let Some((min, max)) = selection.bounds() else {
+        return Vec::new();
+    };
+
+    let blocks = selected_blocks(grid, min, max);
+    (1..=count)
+        .filter_map(|copy| shift(offset, copy))
+        .flat_map(|shift| {
+            blocks.iter().filter_map(move |(position, block_type)| {
+                Some(BlockChange::Place(Place::new(
+                    Some(*block_type),
+                    position.checked_add(shift)?,
+                    true,
+                    None,
+                    NeighbourUpdate::EXTENDED.to_vec(),
+                )))
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn draw_stack_preview(
+    preview: Res<StackPreview>,
+    selection: Res<Selection>,
+    grid: Res<Grid>,
+    mut previewed: Local<PreviewedBlocks>,
+    mut gizmos: Gizmos,
+) {
+    let (Some((count, offset)), Some((min, max))) = (preview.pending, selection.bounds()) else {
+        return;
+    };
+
+    let size = (max - min + IVec3::ONE).as_vec3();
+    let center = (min + max).as_vec3() / 2.0;
+    let blocks = previewed.get(&grid, min, max);
+    let ghosts = blocks.len() * count as usize <= MAX_GHOSTS;
+    let ghost = SKY_400.with_alpha(0.5);
+
+    // The selection may have changed since the preview was checked
+    for shift in (1..=count).map_while(|copy| shift(offset, copy)) {
+        gizmos.cuboid(
+            Transform::from_translation(center + shift.as_vec3()).with_scale(size),
+            SKY_400,
+        );
+
+        if !ghosts {
+            continue;
+        }
+        for position in blocks
+            .iter()
+            .filter_map(|position| position.checked_add(shift))
+        {
+            gizmos.cuboid(
+                Transform::from_translation(position.as_vec3()).with_scale(Vec3::splat(0.8)),
+                ghost,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::{Dirt, Dust};
+
+    #[test]
+    fn previewed_blocks_follow_the_layout() {
+        let mut grid = Grid::default();
+        grid.set(IVec3::ZERO, Some(BlockType::Dirt(Dirt)));
+        grid.set(IVec3::new(1, 0, 0), Some(BlockType::Dust(Dust::default())));
+        grid.set(IVec3::new(40, 0, 0), Some(BlockType::Dirt(Dirt)));
+
+        let mut previewed = PreviewedBlocks::default();
+        let (min, max) = (IVec3::ZERO, IVec3::new(3, 0, 0));
+        assert_eq!(previewed.get(&grid, min, max).len(), 2);
+
+        // Power changes keep the cached positions
+        let key = previewed.key;
+        grid.set(
+            IVec3::new(1, 0, 0),
+            Some(BlockType::Dust(Dust::default().with_power(5))),
+        );
+        assert_eq!(previewed.get(&grid, min, max).len(), 2);
+        assert_eq!(previewed.key, key);
+
+        grid.set(IVec3::new(3, 0, 0), Some(BlockType::Dirt(Dirt)));
+        assert_eq!(previewed.get(&grid, min, max).len(), 3);
+        assert_eq!(previewed.get(&grid, min, IVec3::new(40, 0, 0)).len(), 4);
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use std::mem::discriminant;
+
+use crate::{
+    bindings::{Action, Actions},
+    blocks::NeighbourUpdate,
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, GridMutation, Place, Remove},
+};
+
+/// Player actions kept before the oldest can no longer be undone.
+const MAX_UNDO: usize = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Replay {
+    Undo,
+    Redo,
+}
+
+/// Blocks that appeared, disappeared or turned into another kind of block during one
+/// player action, including what it cascaded into, like a torch dropping off the
+/// block it was attached to. Power and shape changes follow from these and are
+/// recomputed instead.
+#[derive(Default)]
+struct EditAction {
+    mutations: Vec<GridMutation>,
+}
+
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: Vec<EditAction>,
+    redo: Vec<EditAction>,
+    open: Option<EditAction>,
+    replay: Option<Replay>,
+}
+
+fn changes_kind(mutation: &GridMutation) -> bool {
+    mutation.before.as_ref().map(discriminant) != mutation.after.as_ref().map(discriminant)
+}
+
+pub(crate) fn undo_redo(
+    actions: Actions,
+    mut history: ResMut<EditHistory>,
+    mut queue: ResMut<BlockChangeQueue>,
+) {
+    // Let the previous action settle first
+    if history.open.is_some() {
+        return;
+    }
+
+    let (replay, action) = if actions.just_pressed(Action::Undo) {
+        (Replay::Undo, history.undo.pop())
+    } else if actions.just_pressed(Action::Redo) {
+        (Replay::Redo, history.redo.pop())
+    } else {
+        return;
+    };
+
+    let Some(action) = action else {
+        info!("Nothing to {:?}", replay);
+        return;
+    };
+
+    info!("{:?} of {} block changes", replay, action.mutations.len());
+    for mutation in action.mutations.iter().rev() {
+        let neighbor_tick = NeighbourUpdate::EXTENDED.to_vec();
+        let change = match mutation.before {
+            Some(block_type) => BlockChange::Place(Place::new(
+                Some(block_type),
+                mutation.position,
+                true,
+                None,
+                neighbor_tick,
+            )),
+            None => BlockChange::Remove(Remove::new(mutation.position, true, None, neighbor_tick)),
+        };
+        queue.push_input(change);
+    }
+
+    history.replay = Some(replay);
+    history.open = Some(EditAction::default());
+}
+
+pub(crate) fn begin_edit(mut history: ResMut<EditHistory>, queue: Res<BlockChangeQueue>) {
+    if history.open.is_none() && queue.has_player_input() {
+        history.open = Some(EditAction::default());
+    }
+}
+
+/// Collects what the open action changed and closes it once nothing it caused is
+/// still waiting to be applied.
+pub(crate) fn track_edits(
+    mut history: ResMut<EditHistory>,
+    grid: Res<Grid>,
+    queue: Res<BlockChangeQueue>,
+) {
+    let Some(open) = history.open.as_mut() else {
+        return;
+    };
+
+    open.mutations.extend(
+        grid.mutations()
+            .iter()
+            .filter(|mutation| changes_kind(mutation))
+            .cloned(),
+    );
+
+    if queue.has_player_input() || !queue.block_events().is_empty() {
+        return;
+    }
+
+    let replay = history.replay.take();
+    let Some(action) = history.open.take() else {
+        return;
+    };
+
+    if action.mutations.is_empty() {
+        return;
+    }
+
+    match replay {
+        Some(Replay::Undo) => history.redo.push(action),
+        Some(Replay::Redo) => history.undo.push(action),
+        None => {
+            history.undo.push(action);
+            history.redo.clear();
+        }
+    }
+
+    if history.undo.len() > MAX_UNDO {
+        history.undo.remove(0);
+    }
+}
@@ -1,34 +1,50 @@
 use bevy::{ecs::system::SystemParam, prelude::*, window::WindowResolution};
 use bevy_prng::WyRand;
 use bevy_rand::plugin::EntropyPlugin;
-use std::{collections::HashMap, time::Duration};
+use std::collections::HashMap;
 
 use bevy::color::palettes::css::GHOST_WHITE;
 
 use crate::{
+    bindings::{BindingsPlugin, KeyBindings},
     blocks::{BlockType, NeighbourUpdate, StandardGrass},
-    grid_plugin::{BlockChange, Grid, GridPlugin, Place, grid_apply_changes, queue_block_change},
-    interactions::BlockInteractionPlugin,
+    console::ConsolePlugin,
+    grid_plugin::{
+        BlockChange, BlockChangeQueue, Grid, GridPlugin, Place, grid_apply_changes,
+        queue_block_change,
+    },
+    history_plugin::{HistoryPlugin, is_live},
+    interactions::{BlockInteractionPlugin, SLOTS},
     main_camera::MainCameraPlugin,
     materials::redstone::{RedstoneColors, RedstoneMaterials, setup_redstone_materials},
     meshes::{MeshRegistry, setup_mesh_registry},
     redstone::{
         GlobalTick, Scheduler,
-        ticks::{GlobalTickEvent, tick_the_counter},
+        graph::{GraphBackendPlugin, run_compiled_backend},
+        ticks::{GAME_TICKS_PER_SECOND, GlobalTickEvent, tick_the_counter},
     },
     render::{
-        BlockEntities, DirtyRender, RenderPlugin, cleanup, debug_info, hovered_block, renderer,
-        scheduler_info,
+        BlockEntities, DirtyRender, RenderPlugin, backend_info, cleanup, console_info, debug_info,
+        edition_info, hotbar_info, hovered_block, inspector_info, loop_info, place_world_labels,
+        power_labels, preview_placement, renderer, save_list, scheduler_info, schematic_info,
+        selection_info, signal_flow, timeline_info,
     },
+    save::SavePlugin,
+    schematic::SchematicPlugin,
     shaders::block::BlockMaterial,
     systems::recalculate_dirty_blocks,
-    ui::debug_view_system,
+    trace_plugin::TracePlugin,
+    ui::{console_view_system, debug_view_system, hotbar_view_system, trace_view_system},
 };
 
 // mod block_texture_updater;
+mod bindings;
 mod block_position;
 mod blocks;
+mod chunk;
+mod console;
 mod grid_plugin;
+mod history_plugin;
 mod interactions;
 mod main_camera;
 mod materials;
@@ -36,8 +52,13 @@ mod meshes;
 mod pixel_picking_plugin;
 mod redstone;
 mod render;
+mod save;
+mod schematic;
 mod shaders;
 mod systems;
+#[cfg(test)]
+mod testing;
+mod trace_plugin;
 mod ui;
 
 #[derive(Debug)]
@@ -88,6 +109,8 @@ pub struct RenderCtx<'w, 's> {
     pub block_entities: ResMut<'w, BlockEntities>,
 }
 
+/// Input and Render run every frame, Apply to Cleanup once per game tick in
+/// `FixedUpdate`.
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 enum GameLoop {
     Input,
@@ -99,7 +122,7 @@ enum GameLoop {
 
 fn main() {
     App::new()
-        .insert_resource(Time::<Fixed>::from_duration(Duration::from_millis(100)))
+        .insert_resource(Time::<Fixed>::from_hz(GAME_TICKS_PER_SECOND as f64))
         .add_plugins(EntropyPlugin::<WyRand>::default())
         .add_plugins(
             DefaultPlugins
@@ -116,10 +139,17 @@ fn main() {
         )
         .add_plugins(MaterialPlugin::<BlockMaterial>::default())
         .add_plugins((
+            BindingsPlugin,
             GridPlugin,
             MainCameraPlugin,
             BlockInteractionPlugin,
             RenderPlugin,
+            GraphBackendPlugin,
+            HistoryPlugin,
+            SavePlugin,
+            SchematicPlugin,
+            ConsolePlugin,
+            TracePlugin,
         ))
         .init_resource::<Textures>()
         .init_resource::<SelectedBlock>()
@@ -135,38 +165,74 @@ fn main() {
                 setup_mesh_registry,
                 setup_redstone_materials,
                 debug_view_system,
+                console_view_system,
+                hotbar_view_system,
+                trace_view_system,
             )
                 .chain(),
         )
-        .add_systems(FixedUpdate, (tick_the_counter).chain())
-        .add_systems(Update, grid_apply_changes.in_set(GameLoop::Apply))
-        .add_systems(Update, (recalculate_dirty_blocks,).in_set(GameLoop::React))
         .add_systems(
-            Update,
-            (renderer, debug_info, hovered_block, scheduler_info, cleanup)
+            FixedUpdate,
+            (tick_the_counter, run_compiled_backend.run_if(is_live))
                 .chain()
-                .in_set(GameLoop::Render),
+                .before(GameLoop::Apply),
         )
-        .configure_sets(
+        .add_systems(
+            FixedUpdate,
+            grid_apply_changes.in_set(GameLoop::Apply).run_if(is_live),
+        )
+        .add_systems(
+            FixedUpdate,
+            (recalculate_dirty_blocks,)
+                .in_set(GameLoop::React)
+                .run_if(is_live),
+        )
+        .add_systems(
             Update,
             (
-                GameLoop::Input,
-                GameLoop::Apply,
-                GameLoop::React,
-                GameLoop::Cleanup,
-                GameLoop::Render,
+                renderer,
+                debug_info,
+                edition_info,
+                backend_info,
+                timeline_info,
+                save_list,
+                schematic_info,
+                selection_info,
+                console_info,
+                hovered_block,
+                inspector_info,
+                preview_placement,
+                hotbar_info,
+                power_labels,
+                place_world_labels,
+                signal_flow,
+                scheduler_info,
+                loop_info,
+                cleanup,
             )
-                .chain(),
+                .chain()
+                .in_set(GameLoop::Render),
+        )
+        .configure_sets(Update, (GameLoop::Input, GameLoop::Render).chain())
+        .configure_sets(
+            FixedUpdate,
+            (GameLoop::Apply, GameLoop::React, GameLoop::Cleanup).chain(),
         )
         .add_observer(queue_block_change)
         .run();
 }
 
-fn startup(mut commands: Commands, asset_server: Res<AssetServer>, mut textures: ResMut<Textures>) {
+fn startup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut textures: ResMut<Textures>,
+    mut queue: ResMut<BlockChangeQueue>,
+    bindings: Res<KeyBindings>,
+) {
     let fonts = asset_server.load("fonts/retro_gaming.ttf");
 
     commands.spawn((
-        Text::new("(1) Grass  (2) Redstone  (3) Lamp  (4) Dust  (5) Torch    (Space) Center Camera  (Tab) Run/Pause  (R) Reset"),
+        Text::new(bindings.help_text(SLOTS)),
         TextFont {
             font: fonts.clone(),
             font_size: 17.0,
@@ -178,7 +244,7 @@ fn startup(mut commands: Commands, asset_server: Res<AssetServer>, mut textures:
             left: px(15),
             ..default()
         },
-        TextColor(GHOST_WHITE.into())
+        TextColor(GHOST_WHITE.into()),
     ));
 
     let grid_size = 5;
@@ -194,7 +260,8 @@ fn startup(mut commands: Commands, asset_server: Res<AssetServer>, mut textures:
             let position = IVec3::new(pos_x, 0, pos_z);
 
             // TODO: recalculate block here before insertion
-            commands.trigger(BlockChange::Place(Place::new(
+            // Queued as a block event, the starting floor is not a player edit
+            queue.push(BlockChange::Place(Place::new(
                 Some(BlockType::StandardGrass(StandardGrass::default())),
                 position,
                 true,
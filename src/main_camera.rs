@@ -13,10 +13,11 @@ use bevy::{
 
 use bevy::dev_tools::picking_debug::{DebugPickingMode, DebugPickingPlugin};
 
+use crate::bindings::{Action, Actions};
 use crate::pixel_picking_plugin::{OuterCamera, PixelCamera, PixelPickingPlugin};
 
-const RES_WIDTH: u32 = 640;
-const RES_HEIGHT: u32 = 360;
+pub const RES_WIDTH: u32 = 640;
+pub const RES_HEIGHT: u32 = 360;
 
 #[derive(Component)]
 struct Canvas;
@@ -137,10 +138,16 @@ fn startup(
     commands.spawn((Camera2d, Msaa::Off, OuterCamera));
 }
 
+/// Ctrl and the wheel zooms, the wheel alone cycles the hotbar.
 fn camera_zoom(
     mut projection: Single<&mut Projection, With<InnerCamera>>,
     mouse_wheel_input: Res<AccumulatedMouseScroll>,
+    keyboard: Res<ButtonInput<KeyCode>>,
 ) {
+    if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
     let zoom_speed = 0.1;
     if let Projection::Orthographic(ortho) = &mut **projection {
         let delta_zoom = -mouse_wheel_input.delta.y * zoom_speed;
@@ -153,13 +160,12 @@ fn camera_movement(
     mut camera: Single<&mut Transform, With<InnerCamera>>,
     mut camera_settings: ResMut<CameraSettings>,
     mouse_motion: Res<AccumulatedMouseMotion>,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Actions,
 ) {
     let delta = mouse_motion.delta;
     let sensitivity = 0.005;
 
-    if mouse_buttons.pressed(MouseButton::Middle) {
+    if actions.pressed(Action::PanCamera) {
         let pan_speed = 0.01;
         let right = camera.right();
         let up = camera.up();
@@ -172,7 +178,7 @@ fn camera_movement(
         //move center point by same amount
         camera_settings.center += pan;
     }
-    if mouse_buttons.pressed(MouseButton::Right) && keyboard.pressed(KeyCode::ControlLeft) {
+    if actions.pressed(Action::RotateCamera) {
         let (yaw, pitch, roll) = camera.rotation.to_euler(EulerRot::YXZ);
         let yaw = yaw + delta.x * sensitivity;
         let pitch = pitch + delta.y * sensitivity;
@@ -185,10 +191,10 @@ fn camera_movement(
 fn camera_reset(
     mut camera: Single<&mut Transform, With<InnerCamera>>,
     mut camera_settings: ResMut<CameraSettings>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Actions,
     projection: Single<&mut Projection, With<InnerCamera>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if actions.just_pressed(Action::ResetCamera) {
         *camera_settings = CameraSettings::default();
         let camera_origin =
             Transform::from_xyz(10.0, 10.0, 10.0).looking_at(camera_settings.center, Vec3::Y);
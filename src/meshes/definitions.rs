@@ -116,6 +116,16 @@ impl BlockDefinition {
         }],
     };
 
+    pub const DROPPER: Self = Self {
+        parts: &[BlockPart {
+            part: MeshId::Dropper,
+            mesh: PartMesh {
+                size: Vec3::new(0.5, 0.5, 0.5),
+                uvs: UvLayout::Same(STONE_BLOCK),
+            },
+        }],
+    };
+
     pub const STONE_BUTTON: Self = Self {
         parts: &[BlockPart {
             part: MeshId::StoneButton,
@@ -33,6 +33,7 @@ pub fn setup_mesh_registry(mut commands: Commands, mut meshes: ResMut<Assets<Mes
         &BlockDefinition::REDSTONE_LAMP_OFF,
         &BlockDefinition::REDSTONE_BLOCK,
         &BlockDefinition::STONE_BUTTON,
+        &BlockDefinition::DROPPER,
     ];
 
     let registry = build_hash_registry(definitions, &mut meshes);
@@ -50,6 +51,7 @@ pub enum MeshId {
     RedStoneLampOn,
     RedStoneLampOff,
     StoneButton,
+    Dropper,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,252 @@
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use std::collections::VecDeque;
+
+use crate::{
+    blocks::{ALL_DIRS, BlockType, DIRS, RedStoneTorch},
+    grid_plugin::Grid,
+    redstone::{
+        Tick,
+        graph::{CompiledCircuit, Edge, Node, NodeId, NodeKind},
+    },
+};
+
+/// Positions a dust at `position` reads weak power from besides its direct neighbours.
+fn diagonals(position: IVec3) -> impl Iterator<Item = IVec3> {
+    [IVec3::Y, IVec3::NEG_Y]
+        .into_iter()
+        .flat_map(move |y_offset| DIRS.iter().map(move |dir| position + dir + y_offset))
+}
+
+/// A source as it looks while emitting, the graph decides at runtime whether it is.
+fn emitting(block_type: BlockType) -> BlockType {
+    match block_type {
+        BlockType::RedStoneTorch(torch) => BlockType::RedStoneTorch(RedStoneTorch {
+            lit: true,
+            attached_face: torch.attached_face,
+        }),
+        block_type => block_type,
+    }
+}
+
+fn node_kind(block_type: &BlockType) -> Option<NodeKind> {
+    match block_type {
+        BlockType::RedStone(_) => Some(NodeKind::Constant),
+        BlockType::RedStoneTorch(_) => Some(NodeKind::Torch),
+        BlockType::RedStoneLamp(_) => Some(NodeKind::Lamp),
+        BlockType::Dust(_) => Some(NodeKind::Dust),
+        _ => None,
+    }
+}
+
+/// Compiles every redstone component in the grid into a [`CompiledCircuit`]. The
+/// power a source delivers is resolved with the same emission rules the per-block
+/// simulation uses, walking through dust and losing one level per dust hop. `pending`
+/// holds the tile ticks the per-block simulation had scheduled, the graph takes over
+/// the ones belonging to torches.
+pub fn compile(grid: &Grid, now: Tick, pending: &[(Tick, IVec3)]) -> CompiledCircuit {
+    let mut nodes = Vec::new();
+    let mut ids: HashMap<IVec3, NodeId> = HashMap::default();
+    let mut torches_by_attached: HashMap<IVec3, Vec<NodeId>> = HashMap::default();
+
+    for (position, block_data) in grid.iter() {
+        let Some(kind) = node_kind(&block_data.block_type) else {
+            continue;
+        };
+
+        let id = nodes.len();
+        if let BlockType::RedStoneTorch(torch) = block_data.block_type {
+            torches_by_attached
+                .entry(position - torch.attached_face)
+                .or_default()
+                .push(id);
+        }
+
+        ids.insert(position, id);
+        nodes.push(Node {
+            kind,
+            position,
+            block_type: block_data.block_type,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        });
+    }
+
+    // Deterministic node order regardless of chunk iteration order
+    let mut order: Vec<NodeId> = (0..nodes.len()).collect();
+    order.sort_by_key(|id| {
+        let p = nodes[*id].position;
+        (p.x, p.y, p.z)
+    });
+
+    let sources: Vec<NodeId> = order
+        .iter()
+        .copied()
+        .filter(|id| matches!(nodes[*id].kind, NodeKind::Constant | NodeKind::Torch))
+        .collect();
+
+    for source in sources {
+        let delivered = resolve_source(grid, &nodes, &ids, &torches_by_attached, source);
+
+        let mut targets: Vec<_> = delivered.into_iter().collect();
+        targets.sort_unstable();
+        for (target, power) in targets {
+            nodes[target].inputs.push(Edge {
+                source,
+                weight: 15 - power,
+            });
+            nodes[source].outputs.push(target);
+        }
+    }
+
+    let lit = nodes
+        .iter()
+        .map(|node| {
+            matches!(
+                node.block_type,
+                BlockType::RedStoneTorch(RedStoneTorch { lit: true, .. })
+            )
+        })
+        .collect();
+
+    let mut circuit = CompiledCircuit {
+        nodes,
+        lit,
+        now,
+        revision: grid.revision(),
+        ..default()
+    };
+
+    // Only torches that were waiting on a tile tick may flip, one that never heard of
+    // its input changing stays stale like it does in the per-block simulation
+    for (tick, position) in pending {
+        let Some(&id) = ids.get(position) else {
+            continue;
+        };
+        if circuit.nodes[id].kind == NodeKind::Torch && !circuit.is_pending(id) {
+            circuit
+                .pending
+                .entry((*tick).max(now + 1))
+                .or_default()
+                .push(id);
+        }
+    }
+
+    circuit
+}
+
+/// Highest power the source can deliver to every node it reaches.
+fn resolve_source(
+    grid: &Grid,
+    nodes: &[Node],
+    ids: &HashMap<IVec3, NodeId>,
+    torches_by_attached: &HashMap<IVec3, Vec<NodeId>>,
+    source: NodeId,
+) -> HashMap<NodeId, u8> {
+    let source_pos = nodes[source].position;
+    let emitter = emitting(nodes[source].block_type);
+    let is_dust = |pos: IVec3| matches!(grid.get_blocktype(pos), Some(BlockType::Dust(_)));
+
+    let mut dust_power: HashMap<IVec3, u8> = HashMap::default();
+    let mut queue = VecDeque::new();
+
+    let relax = |dust_power: &mut HashMap<IVec3, u8>,
+                 queue: &mut VecDeque<IVec3>,
+                 pos: IVec3,
+                 power: u8| {
+        if power > 0 && dust_power.get(&pos).is_none_or(|current| power > *current) {
+            dust_power.insert(pos, power);
+            queue.push_back(pos);
+        }
+    };
+
+    for dir in ALL_DIRS {
+        let dust_pos = source_pos - dir;
+        let Some(dust) = grid.get_blocktype(dust_pos).filter(|_| is_dust(dust_pos)) else {
+            continue;
+        };
+        let power = emitter
+            .strong_power_emitted_to(dust_pos, source_pos, dust)
+            .max(
+                emitter
+                    .weak_power_emitted(dust_pos, source_pos, dust)
+                    .saturating_sub(1),
+            );
+        relax(&mut dust_power, &mut queue, dust_pos, power);
+    }
+
+    for dust_pos in diagonals(source_pos) {
+        let Some(dust) = grid.get_blocktype(dust_pos).filter(|_| is_dust(dust_pos)) else {
+            continue;
+        };
+        let power = emitter
+            .weak_power_emitted(dust_pos, source_pos, dust)
+            .saturating_sub(1);
+        relax(&mut dust_power, &mut queue, dust_pos, power);
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let power = dust_power[&pos];
+        let neighbours = ALL_DIRS.iter().map(|dir| pos + dir).chain(diagonals(pos));
+        let reachable: HashSet<IVec3> = neighbours.filter(|p| is_dust(*p)).collect();
+        for next in reachable {
+            relax(&mut dust_power, &mut queue, next, power.saturating_sub(1));
+        }
+    }
+
+    let mut delivered: HashMap<NodeId, u8> = HashMap::default();
+    let mut deliver = |target: NodeId, power: u8| {
+        if power > 0 {
+            let current = delivered.entry(target).or_default();
+            *current = (*current).max(power);
+        }
+    };
+
+    for (pos, power) in &dust_power {
+        deliver(ids[pos], *power);
+    }
+
+    // Emitters next to a lamp, or next to the block a torch is attached to
+    let emitters = std::iter::once((source_pos, emitter))
+        .chain(dust_power.iter().map(|(pos, power)| {
+            let BlockType::Dust(dust) = grid.get_blocktype(*pos).copied().unwrap_or(BlockType::Air)
+            else {
+                unreachable!("dust power is only recorded for dust");
+            };
+            (*pos, BlockType::Dust(dust.with_power(*power)))
+        }))
+        .collect::<Vec<_>>();
+
+    for (emitter_pos, emitter) in emitters {
+        for dir in ALL_DIRS {
+            let neighbour_pos = emitter_pos + dir;
+            let Some(neighbour) = grid.get_blocktype(neighbour_pos) else {
+                continue;
+            };
+
+            if let BlockType::RedStoneLamp(_) = neighbour {
+                let power = emitter
+                    .strong_power_emitted_to(neighbour_pos, emitter_pos, neighbour)
+                    .max(
+                        emitter
+                            .weak_power_emitted(neighbour_pos, emitter_pos, neighbour)
+                            .saturating_sub(1),
+                    );
+                deliver(ids[&neighbour_pos], power);
+            }
+
+            if let Some(torches) = torches_by_attached.get(&neighbour_pos) {
+                let power = emitter
+                    .strong_power_emitted_to(neighbour_pos, emitter_pos, neighbour)
+                    .max(emitter.weak_power_emitted(neighbour_pos, emitter_pos, neighbour));
+                for torch in torches {
+                    deliver(*torch, power);
+                }
+            }
+        }
+    }
+
+    delivered
+}
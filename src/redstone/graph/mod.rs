@@ -0,0 +1,460 @@
+use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::prelude::GlobalRng;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    BlockData,
+    blocks::{BlockType, RedStoneTorch},
+    grid_plugin::Grid,
+    redstone::{
+        GlobalTick, Scheduler, Tick,
+        ticks::{GAME_TICKS_PER_REDSTONE_TICK, GlobalTickEvent, advance},
+    },
+    render::DirtyRender,
+};
+
+mod compile;
+mod verify;
+
+pub use compile::compile;
+pub use verify::{Mismatch, verify};
+
+pub type NodeId = usize;
+
+const MAX_TICKS_PER_STEP: u32 = 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Constant,
+    Torch,
+    Lamp,
+    Dust,
+}
+
+/// Signal arriving from a source node, `weight` is the power lost on the way.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub source: NodeId,
+    pub weight: u8,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub position: IVec3,
+    pub block_type: BlockType,
+    pub inputs: Vec<Edge>,
+    pub outputs: Vec<NodeId>,
+}
+
+/// The grid compiled into a graph of components. Dust is folded into the weighted
+/// edges between sources and whatever they power, so a tick only touches the torches
+/// that actually change.
+#[derive(Default)]
+pub struct CompiledCircuit {
+    nodes: Vec<Node>,
+    lit: Vec<bool>,
+    pending: BTreeMap<Tick, Vec<NodeId>>,
+    dirty: HashSet<NodeId>,
+    now: Tick,
+    revision: u64,
+}
+
+impl CompiledCircuit {
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Whether a source node is currently emitting.
+    fn is_on(&self, id: NodeId) -> bool {
+        match self.nodes[id].kind {
+            NodeKind::Constant => true,
+            NodeKind::Torch => self.lit[id],
+            _ => false,
+        }
+    }
+
+    pub fn input(&self, id: NodeId) -> u8 {
+        self.nodes[id]
+            .inputs
+            .iter()
+            .filter(|edge| self.is_on(edge.source))
+            .map(|edge| 15u8.saturating_sub(edge.weight))
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn is_pending(&self, id: NodeId) -> bool {
+        self.pending.values().any(|ids| ids.contains(&id))
+    }
+
+    /// Schedules a torch if its input disagrees with its state, one redstone tick out
+    /// like the per-block simulation does.
+    fn schedule_if_unstable(&mut self, id: NodeId) {
+        if self.nodes[id].kind != NodeKind::Torch || self.is_pending(id) {
+            return;
+        }
+
+        let should_be_lit = self.input(id) == 0;
+        if self.lit[id] != should_be_lit {
+            self.pending
+                .entry(self.now + GAME_TICKS_PER_REDSTONE_TICK)
+                .or_default()
+                .push(id);
+        }
+    }
+
+    /// Advances the circuit by one game tick.
+    pub fn tick(&mut self) {
+        self.now += 1;
+        let Some(due) = self.pending.remove(&self.now) else {
+            return;
+        };
+
+        let mut flipped = Vec::new();
+        for id in due {
+            let lit = self.input(id) == 0;
+            if self.lit[id] != lit {
+                self.lit[id] = lit;
+                self.dirty.insert(id);
+                flipped.push(id);
+            }
+        }
+
+        for id in flipped {
+            for out in self.nodes[id].outputs.clone() {
+                self.dirty.insert(out);
+                self.schedule_if_unstable(out);
+            }
+        }
+    }
+
+    /// The block a node should be rendered as given the current state of the circuit.
+    pub fn block_type(&self, id: NodeId) -> BlockType {
+        let node = &self.nodes[id];
+        match node.block_type {
+            BlockType::RedStoneTorch(torch) => BlockType::RedStoneTorch(RedStoneTorch {
+                lit: self.lit[id],
+                attached_face: torch.attached_face,
+            }),
+            BlockType::RedStoneLamp(lamp) => {
+                BlockType::RedStoneLamp(lamp.with_power(self.input(id)))
+            }
+            BlockType::Dust(dust) => BlockType::Dust(dust.with_power(self.input(id))),
+            block_type => block_type,
+        }
+    }
+
+    /// Writes every node touched since the last sync back into the grid.
+    pub fn sync(&mut self, grid: &mut Grid, dirty_render: &mut DirtyRender) {
+        for id in std::mem::take(&mut self.dirty) {
+            let position = self.nodes[id].position;
+            let block_type = self.block_type(id);
+            if grid.get_blocktype(position) != Some(&block_type) {
+                grid.insert(position, BlockData { block_type });
+                dirty_render.mark(position);
+            }
+        }
+        self.revision = grid.revision();
+    }
+}
+
+#[derive(Resource)]
+pub struct CompiledBackend {
+    enabled: bool,
+    ticks_per_step: u32,
+    circuit: Option<CompiledCircuit>,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Default for CompiledBackend {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ticks_per_step: 1,
+            circuit: None,
+            mismatches: Vec::new(),
+        }
+    }
+}
+
+impl CompiledBackend {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn ticks_per_step(&self) -> u32 {
+        self.ticks_per_step
+    }
+
+    pub fn mismatches(&self) -> &[Mismatch] {
+        &self.mismatches
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.circuit = None;
+        self.mismatches.clear();
+    }
+
+    pub fn faster(&mut self) {
+        self.ticks_per_step = (self.ticks_per_step * 10).min(MAX_TICKS_PER_STEP);
+    }
+
+    pub fn slower(&mut self) {
+        self.ticks_per_step = (self.ticks_per_step / 10).max(1);
+    }
+
+    /// Compiles the grid and checks the graph against the state the per-block
+    /// simulation left behind.
+    pub fn recompile(&mut self, grid: &Grid, now: Tick, pending: &[(Tick, IVec3)]) {
+        let circuit = compile(grid, now, pending);
+        self.mismatches = verify(grid, &circuit);
+        for mismatch in &self.mismatches {
+            warn!(
+                "Compiled backend disagrees with reference at {}: expected {}, got {}",
+                mismatch.position, mismatch.expected, mismatch.actual
+            );
+        }
+        self.circuit = Some(circuit);
+    }
+}
+
+pub struct GraphBackendPlugin;
+
+impl Plugin for GraphBackendPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CompiledBackend>();
+    }
+}
+
+pub fn run_compiled_backend(
+    mut backend: ResMut<CompiledBackend>,
+    mut grid: ResMut<Grid>,
+    mut tick_counter: ResMut<GlobalTick>,
+    mut scheduler: ResMut<Scheduler>,
+    mut dirty_render: ResMut<DirtyRender>,
+    mut writer: MessageWriter<GlobalTickEvent>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+) {
+    if !backend.enabled || !tick_counter.should_advance() {
+        return;
+    }
+
+    let stale = backend
+        .circuit
+        .as_ref()
+        .is_none_or(|circuit| circuit.revision != grid.revision());
+    if stale {
+        info!("Compiling grid into redstone graph");
+        // The graph takes over the tile ticks the per-block simulation had pending,
+        // leaving them in the scheduler would run those torches twice
+        let now = tick_counter.read();
+        let pending: Vec<_> = scheduler
+            .take_tile_ticks()
+            .into_iter()
+            .map(|entry| (now + 1, entry.position))
+            .chain(
+                scheduler
+                    .drain_scheduled()
+                    .into_iter()
+                    .map(|(tick, entry)| (tick, entry.position)),
+            )
+            .collect();
+        backend.recompile(&grid, now, &pending);
+    }
+
+    let ticks = backend.ticks_per_step;
+    let Some(circuit) = backend.circuit.as_mut() else {
+        return;
+    };
+
+    for _ in 0..ticks {
+        advance(
+            &mut tick_counter,
+            &mut scheduler,
+            grid.rules(),
+            &mut **rng,
+            &mut writer,
+        );
+        circuit.tick();
+    }
+    circuit.sync(&mut grid, &mut dirty_render);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::{Dirt, Dust, RedStone, RedStoneLamp, StandardGrass, Tickable},
+        testing::TestWorld,
+    };
+
+    /// A redstone block with a torch on top feeding dust into a lamp, a lit torch
+    /// feeding dust into a lamp and a torch switched off by the redstone block, settled.
+    fn sample_circuit() -> TestWorld {
+        let mut world = TestWorld::new();
+        let mut place = |block_type: BlockType, position: IVec3, facing: IVec3| {
+            world.place(block_type, position, facing);
+            world.tick();
+        };
+
+        for x in -1..=5 {
+            for z in -3..=3 {
+                place(
+                    BlockType::StandardGrass(StandardGrass),
+                    IVec3::new(x, -1, z),
+                    IVec3::Y,
+                );
+            }
+        }
+
+        place(BlockType::RedStone(RedStone), IVec3::ZERO, IVec3::Y);
+        for x in 1..=3 {
+            place(
+                BlockType::Dust(Dust::default()),
+                IVec3::new(x, 0, 0),
+                IVec3::Y,
+            );
+        }
+        place(
+            BlockType::RedStoneLamp(RedStoneLamp::default()),
+            IVec3::new(4, 0, 0),
+            IVec3::Y,
+        );
+
+        place(BlockType::Dirt(Dirt), IVec3::new(0, 0, 2), IVec3::Y);
+        place(
+            BlockType::RedStoneTorch(RedStoneTorch::default()),
+            IVec3::new(1, 0, 2),
+            IVec3::X,
+        );
+        place(
+            BlockType::Dust(Dust::default()),
+            IVec3::new(2, 0, 2),
+            IVec3::Y,
+        );
+        place(
+            BlockType::RedStoneLamp(RedStoneLamp::default()),
+            IVec3::new(3, 0, 2),
+            IVec3::Y,
+        );
+
+        place(BlockType::Dirt(Dirt), IVec3::new(0, 0, -1), IVec3::Y);
+        place(
+            BlockType::RedStoneTorch(RedStoneTorch::default()),
+            IVec3::new(0, 0, -2),
+            IVec3::NEG_Z,
+        );
+        place(
+            BlockType::RedStoneTorch(RedStoneTorch::default()),
+            IVec3::new(0, 1, 0),
+            IVec3::Y,
+        );
+
+        world.run(10);
+
+        let grid = world.grid();
+        assert!(matches!(
+            grid.get_blocktype(IVec3::new(4, 0, 0)),
+            Some(BlockType::RedStoneLamp(lamp)) if lamp.power() > 0
+        ));
+        assert!(matches!(
+            grid.get_blocktype(IVec3::new(3, 0, 2)),
+            Some(BlockType::RedStoneLamp(lamp)) if lamp.power() > 0
+        ));
+        assert!(matches!(
+            grid.get_blocktype(IVec3::new(0, 0, -2)),
+            Some(BlockType::RedStoneTorch(torch)) if !torch.lit
+        ));
+        assert!(matches!(
+            grid.get_blocktype(IVec3::new(0, 1, 0)),
+            Some(BlockType::RedStoneTorch(torch)) if !torch.lit
+        ));
+        world
+    }
+
+    /// Every tile tick still to come, whether the scheduler or the graph holds it.
+    fn pending(world: &TestWorld) -> Vec<(Tick, IVec3)> {
+        let mut pending: Vec<_> = world
+            .scheduler()
+            .pending(world.now())
+            .into_iter()
+            .map(|(tick, position, _)| (tick, position))
+            .collect();
+        if let Some(circuit) = &world.compiled_backend().circuit {
+            pending.extend(
+                circuit.pending.iter().flat_map(|(tick, ids)| {
+                    ids.iter().map(|id| (*tick, circuit.nodes[*id].position))
+                }),
+            );
+        }
+        pending.sort_by_key(|(tick, position)| (*tick, position.x, position.y, position.z));
+        pending
+    }
+
+    #[test]
+    fn compiled_backend_keeps_lock_step_with_the_per_block_simulation() {
+        let mut reference = sample_circuit();
+        let mut compiled = sample_circuit();
+        compiled.toggle_compiled_backend();
+
+        // Toggling the redstone block changes the power along the dust line and in the
+        // lamp and flips the torch that sat on it, so every tick has something to compare
+        let torch_state = |world: &TestWorld| match world.grid().get_blocktype(IVec3::new(0, 1, 0))
+        {
+            Some(BlockType::RedStoneTorch(torch)) => torch.lit,
+            block_type => panic!("expected a torch, found {:?}", block_type),
+        };
+        let mut lit = torch_state(&reference);
+        let mut flips = 0;
+        for step in 0..120 {
+            if step % 12 == 0 {
+                for world in [&mut reference, &mut compiled] {
+                    if step % 24 == 0 {
+                        world.remove(IVec3::ZERO);
+                    } else {
+                        world.place(BlockType::RedStone(RedStone), IVec3::ZERO, IVec3::Y);
+                    }
+                }
+            }
+            reference.tick();
+            compiled.tick();
+
+            assert_eq!(compiled.now(), reference.now());
+            assert_eq!(
+                pending(&compiled),
+                pending(&reference),
+                "on tick {}",
+                reference.now()
+            );
+            assert!(
+                compiled.compiled_backend().mismatches().is_empty(),
+                "{:?}",
+                compiled.compiled_backend().mismatches()
+            );
+            assert_eq!(
+                compiled.grid().iter().count(),
+                reference.grid().iter().count(),
+                "on tick {}",
+                reference.now()
+            );
+            for (position, data) in reference.grid().iter() {
+                assert_eq!(
+                    compiled.grid().get_blocktype(position),
+                    Some(&data.block_type),
+                    "at {} on tick {}",
+                    position,
+                    reference.now()
+                );
+            }
+
+            if torch_state(&reference) != lit {
+                lit = !lit;
+                flips += 1;
+            }
+        }
+        assert_eq!(flips, 10);
+    }
+}
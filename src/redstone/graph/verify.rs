@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::{
+    blocks::{BlockType, Tickable},
+    grid_plugin::Grid,
+    redstone::graph::CompiledCircuit,
+};
+
+/// A node where the compiled graph does not agree with the per-block simulation.
+/// Torches compare `lit` as 0 or 1.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub position: IVec3,
+    pub expected: u8,
+    pub actual: u8,
+}
+
+fn level(block_type: &BlockType) -> Option<u8> {
+    match block_type {
+        BlockType::Dust(dust) => Some(dust.power()),
+        BlockType::RedStoneLamp(lamp) => Some(lamp.power()),
+        BlockType::RedStoneTorch(torch) => Some(torch.lit as u8),
+        _ => None,
+    }
+}
+
+/// Compares the state the graph derives for every node against what the grid holds.
+/// Torches still waiting on their tick are skipped, the reference has not settled
+/// them either.
+pub fn verify(grid: &Grid, circuit: &CompiledCircuit) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+
+    for (id, node) in circuit.nodes().iter().enumerate() {
+        if circuit.is_pending(id) {
+            continue;
+        }
+
+        let Some(expected) = grid.get_blocktype(node.position).and_then(level) else {
+            continue;
+        };
+        let Some(actual) = level(&circuit.block_type(id)) else {
+            continue;
+        };
+
+        if expected != actual {
+            mismatches.push(Mismatch {
+                position: node.position,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    mismatches
+}
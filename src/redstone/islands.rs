@@ -0,0 +1,542 @@
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    tasks::ComputeTaskPool,
+};
+use rand_core::RngCore;
+use std::collections::BTreeMap;
+
+use crate::{
+    BlockData, BlockType,
+    chunk::{CHUNK_SIZE, chunk_coords},
+    grid_plugin::{
+        Grid, GridMutation, TracedUpdate, UpdateLoopGuard, UpdatePass, UpdateTrace,
+        process_immediate,
+    },
+    redstone::{Scheduler, Tick, scheduler::ScheduledTick},
+    render::{DirtyBlocks, DirtyRender},
+};
+
+/// Components further apart than this on any axis can neither power nor update
+/// each other, a torch powering dust through the block it is attached to is the
+/// longest reach there is.
+const REACH: i32 = 2;
+
+fn is_component(block_type: &BlockType) -> bool {
+    matches!(
+        block_type,
+        BlockType::RedStone(_)
+            | BlockType::RedStoneLamp(_)
+            | BlockType::RedStoneTorch(_)
+            | BlockType::Dust(_)
+    )
+}
+
+/// A private copy of everything an island can read, along with the revision of every
+/// chunk it overlaps as of its last copy. `None` chunks have never been copied.
+#[derive(Default)]
+struct Extract {
+    grid: Grid,
+    chunks: Vec<(IVec3, Option<u64>)>,
+}
+
+impl Extract {
+    fn new(min: IVec3, max: IVec3) -> Self {
+        let (chunk_min, chunk_max) = (chunk_coords(min), chunk_coords(max));
+        let mut chunks = Vec::new();
+        for x in chunk_min.x..=chunk_max.x {
+            for y in chunk_min.y..=chunk_max.y {
+                for z in chunk_min.z..=chunk_max.z {
+                    chunks.push((IVec3::new(x, y, z), None));
+                }
+            }
+        }
+
+        let mut grid = Grid::default();
+        grid.record_mutations();
+        Self { grid, chunks }
+    }
+}
+
+/// Connected groups of redstone components that can't reach each other, rebuilt
+/// whenever blocks are added or removed.
+#[derive(Resource, Default)]
+pub struct CircuitIslands {
+    layout_revision: Option<u64>,
+    island_of: HashMap<IVec3, usize>,
+    bounds: Vec<(IVec3, IVec3)>,
+    /// Kept for as long as the layout holds, only chunks that changed since are
+    /// copied again.
+    extracts: Vec<Extract>,
+}
+
+impl CircuitIslands {
+    pub fn island_of(&self, pos: IVec3) -> Option<usize> {
+        self.island_of.get(&pos).copied()
+    }
+
+    pub fn refresh(&mut self, grid: &Grid) {
+        if self.layout_revision == Some(grid.layout_revision()) {
+            return;
+        }
+        self.layout_revision = Some(grid.layout_revision());
+        self.island_of.clear();
+        self.bounds.clear();
+        self.extracts.clear();
+
+        let mut components: Vec<IVec3> = grid
+            .iter()
+            .filter(|(_, data)| is_component(&data.block_type))
+            .map(|(position, _)| position)
+            .collect();
+        // Island ids follow position order, not chunk iteration order
+        components.sort_by_key(|p| (p.x, p.y, p.z));
+        let members: HashSet<IVec3> = components.iter().copied().collect();
+
+        for start in components {
+            if self.island_of.contains_key(&start) {
+                continue;
+            }
+
+            let id = self.bounds.len();
+            let mut bounds = (start, start);
+            let mut stack = vec![start];
+            self.island_of.insert(start, id);
+
+            while let Some(pos) = stack.pop() {
+                bounds = (bounds.0.min(pos), bounds.1.max(pos));
+                for x in -REACH..=REACH {
+                    for y in -REACH..=REACH {
+                        for z in -REACH..=REACH {
+                            let next = pos + IVec3::new(x, y, z);
+                            if members.contains(&next) && !self.island_of.contains_key(&next) {
+                                self.island_of.insert(next, id);
+                                stack.push(next);
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.bounds.push(bounds);
+            let (min, max) = self.region(id);
+            self.extracts.push(Extract::new(min, max));
+        }
+
+        info!("Partitioned grid into {} islands", self.bounds.len());
+    }
+
+    /// Everything the components of an island can read.
+    fn region(&self, island: usize) -> (IVec3, IVec3) {
+        let (min, max) = self.bounds[island];
+        (min - IVec3::splat(REACH), max + IVec3::splat(REACH))
+    }
+
+    /// A private copy of everything an island can read, brought up to date from the
+    /// chunks that changed since it was last handed out. Tile ticks outside any island
+    /// land on passive blocks, they only need the blocks they tick.
+    fn extract(
+        &mut self,
+        grid: &Grid,
+        island: Option<usize>,
+        tile_ticks: &[(usize, IVec3)],
+    ) -> Grid {
+        let Some(island) = island else {
+            let mut extracted = Grid::default();
+            *extracted.rules_mut() = *grid.rules();
+            for (_, position) in tile_ticks {
+                if let Some(block_type) = grid.get_blocktype(*position) {
+                    extracted.insert(
+                        *position,
+                        BlockData {
+                            block_type: *block_type,
+                        },
+                    );
+                }
+            }
+            extracted.record_mutations();
+            return extracted;
+        };
+
+        let (min, max) = self.region(island);
+        let extract = &mut self.extracts[island];
+        *extract.grid.rules_mut() = *grid.rules();
+
+        // The layout is the same as when the copy was made, so only block states can
+        // be out of date and copying over them is enough
+        for (coords, revision) in &mut extract.chunks {
+            let current = grid.chunk_revision(*coords);
+            if revision.is_some() && *revision == current {
+                continue;
+            }
+
+            let origin = *coords * CHUNK_SIZE;
+            let (from, to) = (
+                origin.max(min),
+                (origin + IVec3::splat(CHUNK_SIZE - 1)).min(max),
+            );
+            for (position, data) in grid.range(from, to) {
+                extract.grid.insert(
+                    position,
+                    BlockData {
+                        block_type: data.block_type,
+                    },
+                );
+            }
+            *revision = current;
+        }
+
+        extract.grid.take_mutations();
+        std::mem::take(&mut extract.grid)
+    }
+
+    /// Takes back the copy an island worker ran on. Every change it made was replayed
+    /// onto the grid, which bumped the chunks involved, so those are copied again the
+    /// next time. A worker that did not finish leaves changes that never made it to
+    /// the grid, its copy is dropped instead.
+    fn restore(&mut self, island: Option<usize>, grid: Grid, replayed: bool) {
+        let Some(island) = island else {
+            return;
+        };
+
+        if replayed {
+            self.extracts[island].grid = grid;
+        } else {
+            let (min, max) = self.region(island);
+            self.extracts[island] = Extract::new(min, max);
+        }
+    }
+}
+
+/// Island workers only run with a fixed update order, nothing is ever shuffled.
+struct NoShuffle;
+
+impl RngCore for NoShuffle {
+    fn next_u32(&mut self) -> u32 {
+        unreachable!("island workers never shuffle")
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        unreachable!("island workers never shuffle")
+    }
+
+    fn fill_bytes(&mut self, _dst: &mut [u8]) {
+        unreachable!("island workers never shuffle")
+    }
+}
+
+/// Everything a single tile tick and the updates it caused did, `order` is the
+/// position of the tile tick in the single-threaded execution order.
+struct Cascade {
+    order: usize,
+    updates: Vec<TracedUpdate>,
+    mutations: Vec<GridMutation>,
+    scheduled: Vec<(Tick, ScheduledTick)>,
+}
+
+struct IslandJob {
+    island: Option<usize>,
+    grid: Grid,
+    tile_ticks: Vec<(usize, IVec3)>,
+}
+
+struct IslandResult {
+    island: Option<usize>,
+    grid: Grid,
+    cascades: Vec<Cascade>,
+    dirty_blocks: DirtyBlocks,
+    dirty_render: DirtyRender,
+    updates: usize,
+    completed: bool,
+}
+
+impl IslandJob {
+    fn run(mut self, budget: usize, now: Tick) -> IslandResult {
+        let mut scheduler = Scheduler::default();
+        let mut guard = UpdateLoopGuard::with_budget(budget);
+        let mut trace = UpdateTrace::default();
+        let mut result = IslandResult {
+            island: self.island,
+            grid: Grid::default(),
+            cascades: Vec::new(),
+            dirty_blocks: DirtyBlocks::default(),
+            dirty_render: DirtyRender::default(),
+            updates: 0,
+            completed: true,
+        };
+
+        for (order, position) in self.tile_ticks {
+            scheduler.immediate.push_back(position);
+            let drained = process_immediate(
+                &mut self.grid,
+                &mut scheduler,
+                &mut UpdatePass {
+                    guard: &mut guard,
+                    dirty_blocks: &mut result.dirty_blocks,
+                    dirty_render: &mut result.dirty_render,
+                    trace: &mut trace,
+                },
+                now,
+                &mut NoShuffle,
+            );
+
+            if !drained {
+                result.completed = false;
+                break;
+            }
+
+            result.cascades.push(Cascade {
+                order,
+                updates: trace.take(),
+                mutations: self.grid.take_mutations(),
+                scheduled: scheduler.drain_scheduled(),
+            });
+        }
+
+        result.updates = budget - guard.budget();
+        result.grid = self.grid;
+        result
+    }
+}
+
+/// Runs the due tile ticks of every island on the compute task pool, then replays
+/// their grid mutations and newly scheduled tile ticks in the order the single
+/// threaded loop would have produced them. Since islands can't observe each other,
+/// the outcome is identical, down to scheduler sequence numbers and duplicate
+/// rejection. If an island runs out of budget or hits an update loop, nothing is
+/// applied and the tile ticks are left for the single threaded loop to handle.
+pub fn run_tile_ticks_in_parallel(
+    islands: &mut CircuitIslands,
+    grid: &mut Grid,
+    scheduler: &mut Scheduler,
+    pass: &mut UpdatePass,
+    now: Tick,
+) {
+    let tile_ticks = scheduler.take_tile_ticks();
+
+    let mut by_island: BTreeMap<Option<usize>, Vec<(usize, IVec3)>> = BTreeMap::new();
+    for (order, entry) in tile_ticks.iter().enumerate() {
+        by_island
+            .entry(islands.island_of(entry.position))
+            .or_default()
+            .push((order, entry.position));
+    }
+
+    if by_island.len() < 2 {
+        scheduler.restore_tile_ticks(tile_ticks);
+        return;
+    }
+
+    let jobs: Vec<_> = by_island
+        .into_iter()
+        .map(|(island, tile_ticks)| IslandJob {
+            island,
+            grid: islands.extract(grid, island, &tile_ticks),
+            tile_ticks,
+        })
+        .collect();
+
+    let budget = pass.guard.budget();
+    let results = ComputeTaskPool::get().scope(|scope| {
+        for job in jobs {
+            scope.spawn(async move { job.run(budget, now) });
+        }
+    });
+
+    let updates: usize = results.iter().map(|result| result.updates).sum();
+    if updates > budget || results.iter().any(|result| !result.completed) {
+        info!("Island workers could not finish, falling back to single threaded");
+        for result in results {
+            islands.restore(result.island, result.grid, false);
+        }
+        scheduler.restore_tile_ticks(tile_ticks);
+        return;
+    }
+    pass.guard.spend(updates);
+
+    let mut cascades = Vec::new();
+    for result in results {
+        islands.restore(result.island, result.grid, true);
+        pass.dirty_blocks
+            .positions
+            .extend(result.dirty_blocks.positions);
+        pass.dirty_render
+            .positions
+            .extend(result.dirty_render.positions);
+        cascades.extend(result.cascades);
+    }
+    cascades.sort_by_key(|cascade| cascade.order);
+
+    for cascade in cascades {
+        pass.trace.extend(now, cascade.updates);
+        for mutation in cascade.mutations {
+            grid.set(mutation.position, mutation.after);
+        }
+
+        for (tick, entry) in cascade.scheduled {
+            scheduler.schedule_at(entry.position, tick, entry.priority);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::TaskPool;
+
+    use super::*;
+    use crate::{
+        blocks::{Dirt, Dust, RedStone, RedStoneLamp},
+        redstone::TickPriority,
+    };
+
+    const NOW: Tick = 4;
+
+    /// Redstone blocks next to unpowered dust leading into a lamp, far enough apart to
+    /// be islands of their own, with a tile tick due on the first dust of every line and
+    /// on a block outside any island.
+    fn circuits(count: i32) -> (Grid, Scheduler) {
+        let mut grid = Grid::default();
+        let mut scheduler = Scheduler::default();
+
+        for island in 0..count {
+            let origin = IVec3::new(island * 8, 0, 0);
+            grid.set(origin, Some(BlockType::RedStone(RedStone)));
+            for x in 1..=3 {
+                grid.set(
+                    origin + IVec3::new(x, 0, 0),
+                    Some(BlockType::Dust(Dust::default())),
+                );
+            }
+            grid.set(
+                origin + IVec3::new(4, 0, 0),
+                Some(BlockType::RedStoneLamp(RedStoneLamp::default())),
+            );
+            scheduler.schedule_at(origin + IVec3::X, NOW, TickPriority::Normal);
+        }
+
+        grid.set(IVec3::new(0, 0, 20), Some(BlockType::Dirt(Dirt)));
+        scheduler.schedule_at(IVec3::new(0, 0, 20), NOW, TickPriority::Normal);
+
+        scheduler.advance(NOW, grid.rules(), &mut NoShuffle);
+        (grid, scheduler)
+    }
+
+    fn run_single_threaded(grid: &mut Grid, scheduler: &mut Scheduler, trace: &mut UpdateTrace) {
+        let mut guard = UpdateLoopGuard::with_budget(10_000);
+        while let Some(position) = scheduler.next_tile_tick() {
+            scheduler.immediate.push_back(position);
+            assert!(process_immediate(
+                grid,
+                scheduler,
+                &mut UpdatePass {
+                    guard: &mut guard,
+                    dirty_blocks: &mut DirtyBlocks::default(),
+                    dirty_render: &mut DirtyRender::default(),
+                    trace,
+                },
+                NOW,
+                &mut NoShuffle,
+            ));
+        }
+    }
+
+    fn run_in_parallel(
+        islands: &mut CircuitIslands,
+        grid: &mut Grid,
+        scheduler: &mut Scheduler,
+        trace: &mut UpdateTrace,
+    ) {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+        islands.refresh(grid);
+        run_tile_ticks_in_parallel(
+            islands,
+            grid,
+            scheduler,
+            &mut UpdatePass {
+                guard: &mut UpdateLoopGuard::with_budget(10_000),
+                dirty_blocks: &mut DirtyBlocks::default(),
+                dirty_render: &mut DirtyRender::default(),
+                trace,
+            },
+            NOW,
+        );
+        assert!(
+            scheduler.next_tile_tick().is_none(),
+            "islands fell back to single threaded"
+        );
+    }
+
+    fn blocks(grid: &Grid) -> HashMap<IVec3, BlockType> {
+        grid.iter()
+            .map(|(position, data)| (position, data.block_type))
+            .collect()
+    }
+
+    fn updates(trace: &UpdateTrace) -> Vec<IVec3> {
+        trace
+            .latest()
+            .map(|tick| tick.updates.iter().map(|update| update.position).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn parallel_islands_match_single_threaded_run() {
+        let (mut single, mut single_scheduler) = circuits(4);
+        let mut single_trace = UpdateTrace::default();
+        run_single_threaded(&mut single, &mut single_scheduler, &mut single_trace);
+
+        let (mut parallel, mut parallel_scheduler) = circuits(4);
+        let mut parallel_trace = UpdateTrace::default();
+        run_in_parallel(
+            &mut CircuitIslands::default(),
+            &mut parallel,
+            &mut parallel_scheduler,
+            &mut parallel_trace,
+        );
+
+        assert!(matches!(
+            single.get_blocktype(IVec3::new(4, 0, 0)),
+            Some(BlockType::RedStoneLamp(lamp)) if *lamp != RedStoneLamp::default()
+        ));
+        assert_eq!(blocks(&parallel), blocks(&single));
+        assert_eq!(
+            parallel_scheduler.pending(NOW),
+            single_scheduler.pending(NOW)
+        );
+        assert_eq!(updates(&parallel_trace), updates(&single_trace));
+    }
+
+    #[test]
+    fn cached_extracts_pick_up_changes_made_outside_the_islands() {
+        let mut islands = CircuitIslands::default();
+        let (mut grid, mut scheduler) = circuits(2);
+        let mut trace = UpdateTrace::default();
+        run_in_parallel(&mut islands, &mut grid, &mut scheduler, &mut trace);
+
+        // Unpowered again without touching the layout, as another phase of the tick
+        // would
+        let dust = IVec3::X;
+        grid.set(dust, Some(BlockType::Dust(Dust::default())));
+        let (mut single, mut single_scheduler) = (Grid::default(), Scheduler::default());
+        for (position, data) in grid.iter() {
+            single.set(position, Some(data.block_type));
+        }
+
+        for scheduler in [&mut scheduler, &mut single_scheduler] {
+            scheduler.schedule_at(dust, NOW + 1, TickPriority::Normal);
+            scheduler.schedule_at(dust + IVec3::new(8, 0, 0), NOW + 1, TickPriority::Normal);
+            scheduler.advance(NOW + 1, grid.rules(), &mut NoShuffle);
+        }
+        let layout = grid.layout_revision();
+
+        run_in_parallel(&mut islands, &mut grid, &mut scheduler, &mut trace);
+        run_single_threaded(
+            &mut single,
+            &mut single_scheduler,
+            &mut UpdateTrace::default(),
+        );
+
+        assert_eq!(grid.layout_revision(), layout);
+        assert_eq!(blocks(&grid), blocks(&single));
+    }
+}
@@ -19,6 +19,65 @@ pub enum JunctionType {
     Cross,
 }
 
+/// Neighbour directions in the order connections are listed.
+const SIDES: [IVec3; 4] = [IVec3::NEG_Z, IVec3::Z, IVec3::X, IVec3::NEG_X];
+
+impl JunctionType {
+    pub const ALL: [JunctionType; 12] = [
+        JunctionType::Dot,
+        JunctionType::Vertical,
+        JunctionType::Horizontal,
+        JunctionType::CornerNE,
+        JunctionType::CornerNW,
+        JunctionType::CornerSE,
+        JunctionType::CornerSW,
+        JunctionType::TNorth,
+        JunctionType::TSouth,
+        JunctionType::TEast,
+        JunctionType::TWest,
+        JunctionType::Cross,
+    ];
+
+    /// The sides this shape connects to, north, south, east and west.
+    pub fn connections(&self) -> [bool; 4] {
+        match self {
+            JunctionType::Dot => [false, false, false, false],
+            JunctionType::Vertical => [true, true, false, false],
+            JunctionType::Horizontal => [false, false, true, true],
+            JunctionType::CornerNE => [false, true, true, false],
+            JunctionType::CornerNW => [false, true, false, true],
+            JunctionType::CornerSE => [true, false, true, false],
+            JunctionType::CornerSW => [true, false, false, true],
+            JunctionType::TNorth => [true, false, true, true],
+            JunctionType::TSouth => [false, true, true, true],
+            JunctionType::TEast => [true, true, true, false],
+            JunctionType::TWest => [true, true, false, true],
+            JunctionType::Cross => [true, true, true, true],
+        }
+    }
+
+    /// Directions of the connected sides.
+    pub fn sides(&self) -> impl Iterator<Item = IVec3> {
+        SIDES
+            .into_iter()
+            .zip(self.connections())
+            .filter_map(|(side, connected)| connected.then_some(side))
+    }
+
+    /// The shape with its connections turned by `transform`.
+    pub fn transformed(&self, transform: impl Fn(IVec3) -> IVec3) -> JunctionType {
+        let mut connections = [false; 4];
+        for (side, connected) in SIDES.iter().zip(self.connections()) {
+            if let Some(index) = SIDES.iter().position(|s| *s == transform(*side))
+                && connected
+            {
+                connections[index] = true;
+            }
+        }
+        get_junction_type(&connections)
+    }
+}
+
 fn get_junction_type(connections: &[bool]) -> JunctionType {
     // N, S, E, W
     match connections {
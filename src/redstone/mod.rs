@@ -1,15 +1,22 @@
 use bevy::prelude::*;
 
+pub mod graph;
+pub mod islands;
 pub mod junctions;
+pub mod rules;
 pub mod ticks;
 
 mod scheduler;
-pub use scheduler::{NotifyDelay, Scheduler, Tick};
+pub use scheduler::{NotifyDelay, Scheduler, Tick, TickPriority};
 
+use crate::redstone::ticks::GAME_TICKS_PER_REDSTONE_TICK;
+
+/// Counts game ticks, redstone ticks are derived from it.
 #[derive(Resource, Default, Debug)]
 pub struct GlobalTick {
     counter: Tick,
     is_running: bool,
+    steps: Tick,
 }
 
 impl GlobalTick {
@@ -21,6 +28,15 @@ impl GlobalTick {
         self.counter
     }
 
+    /// Moves the counter to an earlier or later tick, used when scrubbing through history.
+    pub fn set(&mut self, tick: Tick) {
+        self.counter = tick;
+    }
+
+    pub fn redstone_tick(&self) -> Tick {
+        self.counter / GAME_TICKS_PER_REDSTONE_TICK
+    }
+
     pub fn is_running(&self) -> bool {
         self.is_running
     }
@@ -31,6 +47,24 @@ impl GlobalTick {
 
     pub fn stop(&mut self) {
         self.is_running = false;
+        self.steps = 0;
+    }
+
+    /// Runs the given number of ticks while paused, one per fixed update.
+    pub fn step(&mut self, ticks: Tick) {
+        self.steps = self.steps.saturating_add(ticks);
+    }
+
+    /// Whether the counter should advance this fixed update.
+    pub fn should_advance(&mut self) -> bool {
+        if self.is_running {
+            return true;
+        }
+        if self.steps == 0 {
+            return false;
+        }
+        self.steps -= 1;
+        true
     }
 }
 
@@ -1,53 +1,252 @@
 use bevy::prelude::*;
-use std::collections::VecDeque;
+use rand_core::RngCore;
+use std::collections::{BTreeMap, VecDeque};
 
-use bevy::{
-    math::IVec3,
-    platform::collections::{HashMap, HashSet},
+use bevy::math::IVec3;
+
+use crate::redstone::{
+    rules::{SimulationRules, shuffle},
+    ticks::GAME_TICKS_PER_REDSTONE_TICK,
 };
 
 pub type Tick = u64;
 
+/// Delays are expressed in redstone ticks, the scheduler converts them to game ticks.
 #[derive(Debug, Clone, PartialEq)]
 pub enum NotifyDelay {
     Immediate,
     NextTick,
     In(Tick),
+    Prioritized(Tick, TickPriority),
+}
+
+impl NotifyDelay {
+    pub fn priority(&self) -> TickPriority {
+        match self {
+            NotifyDelay::Prioritized(_, priority) => *priority,
+            _ => TickPriority::Normal,
+        }
+    }
+}
+
+/// Tile tick priorities as used by Java Edition, tile ticks due on the same tick
+/// run from the highest to the lowest priority.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TickPriority {
+    ExtremelyHigh,
+    VeryHigh,
+    High,
+    #[default]
+    Normal,
+    Low,
+    VeryLow,
+    ExtremelyLow,
 }
 
-#[derive(Resource, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub position: IVec3,
+    pub priority: TickPriority,
+    pub sequence: u64,
+}
+
+#[derive(Resource, Default, Clone, PartialEq)]
 pub struct Scheduler {
     pub immediate: VecDeque<IVec3>,
-    scheduled: HashMap<u64, HashSet<IVec3>>,
+    tile_ticks: VecDeque<ScheduledTick>,
+    scheduled: BTreeMap<Tick, Vec<ScheduledTick>>,
+    sequence: u64,
 }
 
 impl Scheduler {
-    pub fn schedule(&mut self, pos: IVec3, delay: &NotifyDelay, now: Tick) {
+    /// Returns false if the position already has a tile tick scheduled for the same tick.
+    pub fn schedule(&mut self, pos: IVec3, delay: &NotifyDelay, now: Tick) -> bool {
         match delay {
             NotifyDelay::Immediate => {
                 self.immediate.push_back(pos);
+                true
             }
             NotifyDelay::NextTick => {
-                self.schedule_at(pos, now + 1);
+                self.schedule_at(pos, now + GAME_TICKS_PER_REDSTONE_TICK, delay.priority())
             }
-            NotifyDelay::In(ticks) => {
-                self.schedule_at(pos, now + ticks);
+            NotifyDelay::In(ticks) | NotifyDelay::Prioritized(ticks, _) => self.schedule_at(
+                pos,
+                now + ticks * GAME_TICKS_PER_REDSTONE_TICK,
+                delay.priority(),
+            ),
+        }
+    }
+
+    /// Moves every tile tick due at or before `now` into the tile tick phase, ordered
+    /// by priority and then by the order they were scheduled in, or randomly for Bedrock.
+    pub fn advance(&mut self, now: Tick, rules: &SimulationRules, rng: &mut impl RngCore) {
+        if !rules.runs_tile_ticks_on(now) {
+            return;
+        }
+
+        let later = self.scheduled.split_off(&(now + 1));
+        let due = std::mem::replace(&mut self.scheduled, later);
+        for (_, mut entries) in due {
+            if rules.randomized_update_order() {
+                shuffle(&mut entries, rng);
+            } else {
+                entries.sort_by_key(|entry| (entry.priority, entry.sequence));
             }
+            self.tile_ticks.extend(entries);
         }
     }
 
-    pub fn advance(&mut self, now: Tick) {
-        let positions = self.scheduled.remove(&now).unwrap_or_default();
-        self.immediate.extend(positions);
+    pub fn next_tile_tick(&mut self) -> Option<IVec3> {
+        self.tile_ticks.pop_front().map(|entry| entry.position)
+    }
+
+    /// Takes every tile tick due this tick, in execution order.
+    pub(crate) fn take_tile_ticks(&mut self) -> Vec<ScheduledTick> {
+        self.tile_ticks.drain(..).collect()
     }
 
-    fn schedule_at(&mut self, pos: IVec3, tick: u64) {
-        self.scheduled.entry(tick).or_default().insert(pos);
+    /// Puts tile ticks back in front of the queue, keeping their order.
+    pub(crate) fn restore_tile_ticks(&mut self, entries: Vec<ScheduledTick>) {
+        for entry in entries.into_iter().rev() {
+            self.tile_ticks.push_front(entry);
+        }
     }
 
-    pub fn immediate_queue(&self) -> impl Iterator<Item = (&u64, &HashSet<IVec3>)> {
-        let mut queue: Vec<_> = self.scheduled.iter().collect();
-        queue.sort_by_key(|(tick, _)| *tick);
-        queue.into_iter()
+    /// Removes every pending tile tick, in the order they were scheduled.
+    pub(crate) fn drain_scheduled(&mut self) -> Vec<(Tick, ScheduledTick)> {
+        let mut entries: Vec<_> = std::mem::take(&mut self.scheduled)
+            .into_iter()
+            .flat_map(|(tick, entries)| entries.into_iter().map(move |entry| (tick, entry)))
+            .collect();
+        entries.sort_by_key(|(_, entry)| entry.sequence);
+        entries
+    }
+
+    pub(crate) fn schedule_at(&mut self, pos: IVec3, tick: Tick, priority: TickPriority) -> bool {
+        let entries = self.scheduled.entry(tick).or_default();
+        if entries.iter().any(|entry| entry.position == pos) {
+            info!("Rejecting duplicate tile tick for {} at tick {}", pos, tick);
+            return false;
+        }
+
+        entries.push(ScheduledTick {
+            position: pos,
+            priority,
+            sequence: self.sequence,
+        });
+        self.sequence += 1;
+        true
+    }
+
+    /// Every tile tick that hasn't run yet, those already due at `now` first and the
+    /// rest in the order they were scheduled.
+    pub fn pending(&self, now: Tick) -> Vec<(Tick, IVec3, TickPriority)> {
+        let due = self
+            .tile_ticks
+            .iter()
+            .map(|entry| (now, entry.position, entry.priority));
+
+        let mut scheduled: Vec<_> = self
+            .scheduled
+            .iter()
+            .flat_map(|(tick, entries)| entries.iter().map(move |entry| (*tick, *entry)))
+            .collect();
+        scheduled.sort_by_key(|(_, entry)| entry.sequence);
+
+        due.chain(
+            scheduled
+                .into_iter()
+                .map(|(tick, entry)| (tick, entry.position, entry.priority)),
+        )
+        .collect()
+    }
+
+    /// Pending tile ticks in the order they will be executed.
+    pub fn immediate_queue(&self) -> impl Iterator<Item = (Tick, Vec<ScheduledTick>)> + '_ {
+        self.scheduled.iter().map(|(tick, entries)| {
+            let mut entries = entries.clone();
+            entries.sort_by_key(|entry| (entry.priority, entry.sequence));
+            (*tick, entries)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Java never asks for randomness.
+    struct NoRng;
+
+    impl RngCore for NoRng {
+        fn next_u32(&mut self) -> u32 {
+            unreachable!()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            unreachable!()
+        }
+
+        fn fill_bytes(&mut self, _dst: &mut [u8]) {
+            unreachable!()
+        }
+    }
+
+    fn drain_tile_ticks(scheduler: &mut Scheduler) -> Vec<IVec3> {
+        std::iter::from_fn(|| scheduler.next_tile_tick()).collect()
+    }
+
+    #[test]
+    fn advance_orders_by_priority_then_sequence() {
+        let mut scheduler = Scheduler::default();
+        let rules = SimulationRules::default();
+        scheduler.schedule_at(IVec3::X, 4, TickPriority::Normal);
+        scheduler.schedule_at(IVec3::Y, 4, TickPriority::Low);
+        scheduler.schedule_at(IVec3::Z, 4, TickPriority::High);
+        scheduler.schedule_at(IVec3::NEG_X, 4, TickPriority::Normal);
+        scheduler.schedule_at(IVec3::NEG_Y, 4, TickPriority::ExtremelyHigh);
+
+        scheduler.advance(4, &rules, &mut NoRng);
+
+        assert_eq!(
+            drain_tile_ticks(&mut scheduler),
+            [IVec3::NEG_Y, IVec3::Z, IVec3::X, IVec3::NEG_X, IVec3::Y]
+        );
+    }
+
+    #[test]
+    fn advance_runs_earlier_ticks_first_and_keeps_later_ones() {
+        let mut scheduler = Scheduler::default();
+        let rules = SimulationRules::default();
+        scheduler.schedule_at(IVec3::X, 3, TickPriority::Low);
+        scheduler.schedule_at(IVec3::Y, 2, TickPriority::Low);
+        scheduler.schedule_at(IVec3::Z, 1, TickPriority::Normal);
+        scheduler.schedule_at(IVec3::NEG_X, 2, TickPriority::High);
+
+        scheduler.advance(2, &rules, &mut NoRng);
+        assert_eq!(
+            drain_tile_ticks(&mut scheduler),
+            [IVec3::Z, IVec3::NEG_X, IVec3::Y]
+        );
+
+        scheduler.advance(3, &rules, &mut NoRng);
+        assert_eq!(drain_tile_ticks(&mut scheduler), [IVec3::X]);
+    }
+
+    #[test]
+    fn pending_keeps_the_priority_of_due_tile_ticks() {
+        let mut scheduler = Scheduler::default();
+        scheduler.schedule_at(IVec3::X, 2, TickPriority::VeryHigh);
+        scheduler.schedule_at(IVec3::Y, 6, TickPriority::Low);
+
+        scheduler.advance(2, &SimulationRules::default(), &mut NoRng);
+
+        assert_eq!(
+            scheduler.pending(2),
+            [
+                (2, IVec3::X, TickPriority::VeryHigh),
+                (6, IVec3::Y, TickPriority::Low),
+            ]
+        );
     }
 }
@@ -1,6 +1,35 @@
 use bevy::prelude::*;
+use bevy_prng::WyRand;
+use bevy_rand::prelude::GlobalRng;
+use rand_core::RngCore;
 
-use crate::redstone::{GlobalTick, Scheduler, Tick};
+use crate::{
+    grid_plugin::Grid,
+    redstone::{GlobalTick, Scheduler, Tick, graph::CompiledBackend, rules::SimulationRules},
+};
+
+pub const GAME_TICKS_PER_SECOND: u64 = 20;
+pub const GAME_TICKS_PER_REDSTONE_TICK: Tick = 2;
+
+/// The order in which work is processed within a game tick, mirroring Java Edition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TickPhase {
+    /// Scheduled tile ticks that are due, in the order they were scheduled.
+    TileTicks,
+    /// Changes requested by blocks themselves, e.g. neighbour reactions.
+    BlockEvents,
+    /// Placements and removals requested by the player.
+    PlayerInput,
+}
+
+impl TickPhase {
+    /// Every phase, in the order they run within a tick.
+    pub const ALL: [TickPhase; 3] = [
+        TickPhase::TileTicks,
+        TickPhase::BlockEvents,
+        TickPhase::PlayerInput,
+    ];
+}
 
 #[derive(Message)]
 pub struct GlobalTickEvent(Tick);
@@ -9,11 +38,36 @@ pub fn tick_the_counter(
     mut tick_counter: ResMut<GlobalTick>,
     mut scheduler: ResMut<Scheduler>,
     mut writer: MessageWriter<GlobalTickEvent>,
+    mut rng: Single<&mut WyRand, With<GlobalRng>>,
+    grid: Res<Grid>,
+    backend: Res<CompiledBackend>,
 ) {
-    if tick_counter.is_running() {
-        tick_counter.tick();
-        let now = tick_counter.read();
-        scheduler.advance(now);
-        writer.write(GlobalTickEvent(now));
+    // The compiled backend advances the counter itself while enabled
+    if backend.is_enabled() {
+        return;
     }
+
+    if tick_counter.should_advance() {
+        advance(
+            &mut tick_counter,
+            &mut scheduler,
+            grid.rules(),
+            &mut **rng,
+            &mut writer,
+        );
+    }
+}
+
+/// Moves the counter one game tick forward, keeping the scheduler in step.
+pub(crate) fn advance(
+    tick_counter: &mut GlobalTick,
+    scheduler: &mut Scheduler,
+    rules: &SimulationRules,
+    rng: &mut impl RngCore,
+    writer: &mut MessageWriter<GlobalTickEvent>,
+) {
+    tick_counter.tick();
+    let now = tick_counter.read();
+    scheduler.advance(now, rules, rng);
+    writer.write(GlobalTickEvent(now));
 }
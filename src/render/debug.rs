@@ -1,16 +1,87 @@
 use bevy::prelude::*;
 
 use crate::{
-    blocks::Tickable,
-    grid_plugin::Grid,
-    interactions::HoveredBlockInfo,
-    redstone::{GlobalTick, Scheduler},
-    ui::{BlockPosInfo, BlockPowerInfo, Immediate, TickText},
+    blocks::{Block, Tickable},
+    console::Console,
+    grid_plugin::{Grid, UpdateLoopGuard, UpdateTrace},
+    history_plugin::History,
+    interactions::{Clipboard, Field, HoveredBlockInfo, Inspector, Selection, block_name},
+    redstone::{GlobalTick, Scheduler, graph::CompiledBackend},
+    save::SaveSlots,
+    schematic::SchematicReport,
+    trace_plugin::{TRACE_LINES, TracePanel, trace_lines},
+    ui::{
+        BackendText, BlockPosInfo, BlockPowerInfo, ConsolePanel, ConsoleText, EditionText,
+        Immediate, InspectorText, LoopInfo, SaveList, SchematicInfo, SelectionText, TickText,
+        TimelineFill, TimelineText, TraceText, TraceView,
+    },
 };
 
 pub fn debug_info(tick_counter: Res<GlobalTick>, mut query: Query<&mut TextSpan, With<TickText>>) {
     for mut span in &mut query {
-        **span = format!("{}", tick_counter.read());
+        **span = format!(
+            "{} (redstone: {})",
+            tick_counter.read(),
+            tick_counter.redstone_tick()
+        );
+    }
+}
+
+pub fn edition_info(grid: Res<Grid>, mut query: Query<&mut TextSpan, With<EditionText>>) {
+    for mut span in &mut query {
+        let rules = grid.rules();
+        let quasi_connectivity = if rules.quasi_connectivity() {
+            "on"
+        } else {
+            "off"
+        };
+        **span = format!("{:?} (QC {})", rules.edition, quasi_connectivity);
+    }
+}
+
+pub fn backend_info(
+    backend: Res<CompiledBackend>,
+    mut query: Query<&mut TextSpan, With<BackendText>>,
+) {
+    for mut span in &mut query {
+        **span = if backend.is_enabled() {
+            format!(
+                "Compiled x{} ({} mismatches)",
+                backend.ticks_per_step(),
+                backend.mismatches().len()
+            )
+        } else {
+            "Per-block".to_string()
+        };
+    }
+}
+
+pub fn timeline_info(
+    history: Res<History>,
+    mut texts: Query<&mut TextSpan, With<TimelineText>>,
+    mut fills: Query<&mut Node, With<TimelineFill>>,
+) {
+    let first = history.tick_at(0).unwrap_or_default();
+    let last = history
+        .tick_at(history.recorded_ticks().saturating_sub(1))
+        .unwrap_or_default();
+    let current = history.tick_at(history.position()).unwrap_or_default();
+
+    for mut span in &mut texts {
+        **span = if history.is_rewound() {
+            format!("{} of {}..{} (Tab resumes)", current, first, last)
+        } else {
+            format!("live ({} ticks recorded)", history.recorded_ticks())
+        };
+    }
+
+    let progress = if last > first {
+        (current - first) as f32 / (last - first) as f32 * 100.0
+    } else {
+        100.0
+    };
+    for mut node in &mut fills {
+        node.width = percent(progress);
     }
 }
 
@@ -33,19 +104,78 @@ pub fn hovered_block(
             {
                 let mut power_query = texts.p1();
                 let mut power_span = power_query.single_mut().unwrap();
-                **power_span = format!("{}", block_type.power());
+                let activated = if grid.is_activated(position, block_type.activation()) {
+                    " (activated)"
+                } else {
+                    ""
+                };
+                **power_span = format!("{}{}", block_type.power(), activated);
+            }
+        }
+    }
+}
+
+/// Full state of the inspected block, the field picked for editing is marked, followed
+/// by the tile ticks still pending at its position.
+pub fn inspector_info(
+    inspector: Res<Inspector>,
+    hovered_block_info: Res<HoveredBlockInfo>,
+    grid: Res<Grid>,
+    scheduler: Res<Scheduler>,
+    tick_counter: Res<GlobalTick>,
+    mut query: Query<&mut Text, With<InspectorText>>,
+) {
+    let mut s = String::new();
+    if let Some(position) = inspector.target(&hovered_block_info) {
+        let pinned = if inspector.is_pinned() {
+            " (pinned)"
+        } else {
+            ""
+        };
+        s.push_str(&format!(
+            "(x: {}, y: {}, z: {}){}\n",
+            position.x, position.y, position.z, pinned
+        ));
+
+        if let Some(block_type) = grid.get_blocktype(position) {
+            s.push_str(&format!("{}\n", block_name(block_type)));
+            let fields = Field::of(block_type);
+            for (index, field) in fields.iter().enumerate() {
+                let marker = if index == inspector.field() % fields.len() {
+                    "> "
+                } else {
+                    "  "
+                };
+                s.push_str(&format!(
+                    "{}{}: {}\n",
+                    marker,
+                    field.name(),
+                    field.value(block_type)
+                ));
             }
         }
+
+        for (tick, _, priority) in scheduler
+            .pending(tick_counter.read())
+            .into_iter()
+            .filter(|(_, p, _)| *p == position)
+        {
+            s.push_str(&format!("- {} [{:?}]\n", tick, priority));
+        }
     }
+
+    let mut text = query.single_mut().unwrap();
+    **text = s;
 }
 
 pub fn scheduler_info(scheduler: Res<Scheduler>, mut query: Query<&mut Text, With<Immediate>>) {
     let mut s = String::new();
-    for (tick, set) in scheduler.immediate_queue() {
-        for p in set {
+    for (tick, entries) in scheduler.immediate_queue() {
+        for (order, entry) in entries.iter().enumerate() {
+            let p = entry.position;
             s.push_str(&format!(
-                "- {}: (x: {}, y: {}, z: {})\n",
-                tick, p.x, p.y, p.z
+                "- {} [{:?} #{}]: (x: {}, y: {}, z: {})\n",
+                tick, entry.priority, order, p.x, p.y, p.z
             ));
         }
     }
@@ -53,3 +183,122 @@ pub fn scheduler_info(scheduler: Res<Scheduler>, mut query: Query<&mut Text, Wit
     let mut text = query.single_mut().unwrap();
     **text = s;
 }
+
+pub fn loop_info(guard: Res<UpdateLoopGuard>, mut query: Query<&mut Text, With<LoopInfo>>) {
+    let mut s = String::new();
+    if let Some(update_loop) = guard.detected() {
+        s.push_str(&format!(
+            "Update loop at tick {}, paused (Tab to resume):\n",
+            update_loop.tick
+        ));
+        for p in &update_loop.positions {
+            s.push_str(&format!("- (x: {}, y: {}, z: {})\n", p.x, p.y, p.z));
+        }
+    }
+
+    let mut text = query.single_mut().unwrap();
+    **text = s;
+}
+
+pub fn save_list(slots: Res<SaveSlots>, mut query: Query<&mut Text, With<SaveList>>) {
+    let selected = slots.selected();
+    let mut s = String::new();
+    for file in slots.files() {
+        let marker = if Some(file) == selected { "> " } else { "  " };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        s.push_str(&format!("{}{}\n", marker, name));
+    }
+
+    let mut text = query.single_mut().unwrap();
+    **text = s;
+}
+
+pub fn schematic_info(
+    report: Res<SchematicReport>,
+    mut query: Query<&mut Text, With<SchematicInfo>>,
+) {
+    if !report.is_changed() {
+        return;
+    }
+
+    let mut text = query.single_mut().unwrap();
+    **text = report.message.clone();
+}
+
+pub fn console_info(
+    console: Res<Console>,
+    mut panel: Query<&mut Node, With<ConsolePanel>>,
+    mut query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let mut node = panel.single_mut().unwrap();
+    node.display = if console.is_open() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    let mut s = String::new();
+    for line in console.output() {
+        s.push_str(&format!("{}\n", line));
+    }
+    s.push_str(&format!("/{}_", console.input()));
+
+    let mut text = query.single_mut().unwrap();
+    **text = s;
+}
+
+pub fn trace_info(
+    trace: Res<UpdateTrace>,
+    panel: Res<TracePanel>,
+    mut view: Query<&mut Node, With<TraceView>>,
+    mut query: Query<&mut Text, With<TraceText>>,
+) {
+    let mut node = view.single_mut().unwrap();
+    node.display = if panel.is_open() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    if !panel.is_open() {
+        return;
+    }
+
+    let lines = trace_lines(&trace).count();
+    let end = lines.saturating_sub(panel.scroll());
+    let start = end.saturating_sub(TRACE_LINES);
+
+    let mut s = String::new();
+    if !panel.message().is_empty() {
+        s.push_str(&format!("{}\n", panel.message()));
+    }
+    for line in trace_lines(&trace).skip(start).take(end - start) {
+        s.push_str(&format!("{}\n", line));
+    }
+    s.push_str(&format!("lines {}..{} of {}", start, end, lines));
+
+    let mut text = query.single_mut().unwrap();
+    **text = s;
+}
+
+pub fn selection_info(
+    selection: Res<Selection>,
+    clipboard: Res<Clipboard>,
+    mut query: Query<&mut TextSpan, With<SelectionText>>,
+) {
+    let mode = if selection.is_active() { "on" } else { "off" };
+    let area = match selection.bounds() {
+        Some((min, max)) => {
+            let size = max - min + IVec3::ONE;
+            format!("{}x{}x{}", size.x, size.y, size.z)
+        }
+        None => "none".to_string(),
+    };
+
+    for mut span in &mut query {
+        **span = format!("{} {}, clipboard {} blocks", mode, area, clipboard.len());
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+
+use crate::{
+    SelectedBlock, SpawnCtx, TextureAtlas,
+    blocks::{Block, BlockType, RecomputedResult, Tickable},
+    interactions::{HoveredBlockInfo, PendingFacing, Selection},
+    meshes::{MeshId, MeshRegistry},
+    redstone::{JunctionUVs, get_mesh, junctions::JunctionType},
+};
+
+/// Translucent preview of the block that a left click would place.
+#[derive(Component)]
+pub struct Ghost;
+
+/// Dust lies flat on the floor, every part shares the same transform.
+fn dust_meshes(shape: JunctionType) -> Vec<JunctionUVs> {
+    match shape {
+        JunctionType::Dot => vec![JunctionUVs::Dot],
+        JunctionType::Vertical => vec![JunctionUVs::Vertical],
+        JunctionType::Horizontal => vec![JunctionUVs::Horizontal],
+        shape => {
+            let [north, south, east, west] = shape.connections();
+            let mut parts = vec![JunctionUVs::Dot];
+            for (connected, part) in [
+                (north, JunctionUVs::HLineN),
+                (south, JunctionUVs::HLineS),
+                (east, JunctionUVs::HLineE),
+                (west, JunctionUVs::HLineW),
+            ] {
+                if connected {
+                    parts.push(part);
+                }
+            }
+            parts
+        }
+    }
+}
+
+/// Meshes and transforms a block is drawn with at the position, without its glow.
+pub fn block_meshes(
+    block_type: &BlockType,
+    position: IVec3,
+    mesh_registry: &MeshRegistry,
+    meshes: &mut Assets<Mesh>,
+) -> Vec<(Handle<Mesh>, Transform)> {
+    let mesh_id = match block_type {
+        BlockType::Air => return Vec::new(),
+        BlockType::StandardGrass(_) => MeshId::StandardGrass,
+        BlockType::Dirt(_) => MeshId::StandardDirt,
+        BlockType::RedStone(_) => MeshId::RedStoneBlock,
+        BlockType::Dropper(_) => MeshId::Dropper,
+        BlockType::RedStoneLamp(lamp) if lamp.power() > 0 => MeshId::RedStoneLampOn,
+        BlockType::RedStoneLamp(_) => MeshId::RedStoneLampOff,
+        BlockType::RedStoneTorch(torch) => {
+            let mesh_id = if torch.lit {
+                MeshId::RedstoneTorchStemOn
+            } else {
+                MeshId::RedstoneTorchStemOff
+            };
+            return mesh_registry
+                .get(mesh_id)
+                .map(|mesh| vec![(mesh.clone(), torch.transform(position))])
+                .unwrap_or_default();
+        }
+        BlockType::Dust(dust) => {
+            let transform = Transform::from_translation(
+                position.as_vec3() - (Vec3::Y * 0.5) + (Vec3::Y * 0.01),
+            )
+            .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
+            return dust_meshes(dust.shape)
+                .into_iter()
+                .map(|part| (meshes.add(get_mesh(part)), transform))
+                .collect();
+        }
+    };
+
+    mesh_registry
+        .get(mesh_id)
+        .map(|mesh| {
+            vec![(
+                mesh.clone(),
+                Transform::from_translation(position.as_vec3()),
+            )]
+        })
+        .unwrap_or_default()
+}
+
+/// Shows the selected block at the hovered face, oriented the way placing it would
+/// turn out and tinted red when it can't be placed there. Only respawned when the
+/// preview changes.
+pub fn preview_placement(
+    mut ctx: SpawnCtx,
+    selected_block: Res<SelectedBlock>,
+    hovered_block: Res<HoveredBlockInfo>,
+    selection: Res<Selection>,
+    pending: Res<PendingFacing>,
+    ghosts: Query<Entity, With<Ghost>>,
+    mut shown: Local<Option<(IVec3, BlockType, bool)>>,
+) {
+    let target = match (
+        selected_block.0,
+        hovered_block.position,
+        hovered_block.normal,
+    ) {
+        (Some(block_type), Some(position), Some(normal)) if !selection.is_active() => {
+            let position = position + normal;
+            let facing = pending.resolve(&block_type, normal);
+            let block_type = match block_type.on_placement(&ctx.grid, position, facing) {
+                RecomputedResult::Changed {
+                    new_block: Some(new_block),
+                    ..
+                } => new_block,
+                _ => block_type,
+            };
+            let placeable = block_type.try_place(&ctx.grid, position);
+            Some((position, block_type, placeable))
+        }
+        _ => None,
+    };
+
+    if *shown == target {
+        return;
+    }
+    *shown = target;
+
+    for entity in &ghosts {
+        ctx.commands.entity(entity).despawn();
+    }
+
+    let Some((position, block_type, placeable)) = target else {
+        return;
+    };
+
+    let tint = if placeable {
+        Color::srgba(1.0, 1.0, 1.0, 0.5)
+    } else {
+        Color::srgba(1.0, 0.2, 0.2, 0.6)
+    };
+    let material = ctx.materials.add(StandardMaterial {
+        base_color: tint,
+        base_color_texture: ctx.atlas.handles.get(&TextureAtlas::Blocks).cloned(),
+        alpha_mode: AlphaMode::Blend,
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+
+    for (mesh, transform) in
+        block_meshes(&block_type, position, &ctx.mesh_registry, &mut ctx.meshes)
+    {
+        ctx.commands.spawn((
+            Name::new("Ghost"),
+            Ghost,
+            Mesh3d(mesh),
+            MeshMaterial3d(material.clone()),
+            transform,
+            // Sits in front of the hovered face, it must not take the hover from it
+            Pickable::IGNORE,
+        ));
+    }
+}
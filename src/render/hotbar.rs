@@ -0,0 +1,71 @@
+use bevy::{camera::visibility::RenderLayers, color::palettes::tailwind::YELLOW_400, prelude::*};
+
+use crate::{
+    SpawnCtx, TextureAtlas,
+    blocks::BlockType,
+    interactions::Hotbar,
+    render::block_meshes,
+    ui::{HotbarIcon, HotbarSlot, ICON_LAYER, icon_position},
+};
+
+/// Frames the selected slot and redraws the icons whenever the hotbar changes.
+pub fn hotbar_info(
+    mut ctx: SpawnCtx,
+    hotbar: Res<Hotbar>,
+    icons: Query<Entity, With<HotbarIcon>>,
+    mut frames: Query<(&HotbarSlot, &mut BorderColor)>,
+) {
+    if !hotbar.is_changed() {
+        return;
+    }
+
+    for (slot, mut border) in &mut frames {
+        *border = if hotbar.selected() == Some(slot.0) {
+            BorderColor::all(YELLOW_400)
+        } else {
+            BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.3))
+        };
+    }
+
+    for entity in &icons {
+        ctx.commands.entity(entity).despawn();
+    }
+
+    let texture = ctx.atlas.handles.get(&TextureAtlas::Blocks).cloned();
+    let material = ctx.materials.add(StandardMaterial {
+        base_color_texture: texture.clone(),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+    let dust = ctx.materials.add(StandardMaterial {
+        base_color: Color::srgb(0.8, 0.05, 0.05),
+        base_color_texture: texture,
+        alpha_mode: AlphaMode::Mask(0.5),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+
+    for (slot, block_type) in hotbar.slots().iter().enumerate() {
+        let Some(block_type) = block_type else {
+            continue;
+        };
+        let material = match block_type {
+            BlockType::Dust(_) => dust.clone(),
+            _ => material.clone(),
+        };
+
+        for (mesh, mut transform) in
+            block_meshes(block_type, IVec3::ZERO, &ctx.mesh_registry, &mut ctx.meshes)
+        {
+            transform.translation += icon_position(slot);
+            ctx.commands.spawn((
+                Name::new("Hotbar Icon"),
+                HotbarIcon,
+                Mesh3d(mesh),
+                MeshMaterial3d(material.clone()),
+                transform,
+                RenderLayers::layer(ICON_LAYER),
+            ));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use bevy::{
+    color::palettes::{css::GHOST_WHITE, tailwind::YELLOW_400},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{
+    blocks::{BlockType, Tickable},
+    grid_plugin::{Grid, UpdateTrace},
+    main_camera::{RES_HEIGHT, RES_WIDTH},
+    pixel_picking_plugin::{OuterCamera, PixelCamera},
+    redstone::Tick,
+    render::DirtyRender,
+    trace_plugin::TracePanel,
+    ui::{PowerLabel, TraceLabel, WorldLabel},
+};
+
+/// Blocks numbered at most, a long trace only labels the first ones it reached.
+const MAX_TRACE_LABELS: usize = 128;
+
+/// Power levels shown above the blocks. Labels are UI text at window resolution, so
+/// they stay sharp however low the pixel camera renders.
+#[derive(Resource, Default)]
+pub struct PowerLabels {
+    enabled: bool,
+    entities: HashMap<IVec3, Entity>,
+}
+
+impl PowerLabels {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Dust shows every level, other blocks only while they give off power.
+fn label_text(block_type: &BlockType) -> Option<String> {
+    match block_type {
+        BlockType::Dust(dust) => Some(dust.power().to_string()),
+        block_type if block_type.power() > 0 => Some(block_type.power().to_string()),
+        _ => None,
+    }
+}
+
+/// Relabels the positions in `DirtyRender`, or every block when the overlay is
+/// switched on.
+pub fn power_labels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid: Res<Grid>,
+    dirty_render: Res<DirtyRender>,
+    mut labels: ResMut<PowerLabels>,
+    mut texts: Query<&mut Text, With<PowerLabel>>,
+    mut shown: Local<bool>,
+) {
+    let enabled = labels.is_enabled();
+    if *shown != enabled {
+        *shown = enabled;
+        for (_, entity) in labels.entities.drain() {
+            commands.entity(entity).despawn();
+        }
+        if !enabled {
+            return;
+        }
+
+        let fonts: Handle<Font> = asset_server.load("fonts/retro_gaming.ttf");
+        for (position, data) in grid.iter() {
+            if let Some(text) = label_text(&data.block_type) {
+                let entity = spawn_label(&mut commands, fonts.clone(), position, text);
+                labels.entities.insert(position, entity);
+            }
+        }
+        return;
+    }
+
+    if !enabled || dirty_render.positions.is_empty() {
+        return;
+    }
+
+    let fonts: Handle<Font> = asset_server.load("fonts/retro_gaming.ttf");
+    for position in &dirty_render.positions {
+        let text = grid.get_blocktype(*position).and_then(label_text);
+        let entity = labels.entities.get(position).copied();
+
+        match (text, entity) {
+            (Some(text), Some(entity)) => {
+                if let Ok(mut label) = texts.get_mut(entity) {
+                    **label = text;
+                }
+            }
+            (Some(text), None) => {
+                let entity = spawn_label(&mut commands, fonts.clone(), *position, text);
+                labels.entities.insert(*position, entity);
+            }
+            (None, Some(entity)) => {
+                commands.entity(entity).despawn();
+                labels.entities.remove(position);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn spawn_label(
+    commands: &mut Commands,
+    fonts: Handle<Font>,
+    position: IVec3,
+    text: String,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new("Power Label"),
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::horizontal(px(2)),
+                display: Display::None,
+                ..default()
+            },
+            Text::new(text),
+            TextFont {
+                font: fonts,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(GHOST_WHITE.into()),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            Pickable::IGNORE,
+            WorldLabel(position.as_vec3() + Vec3::Y * 0.6),
+            PowerLabel,
+        ))
+        .id()
+}
+
+/// Numbers the blocks of the latest traced tick in the order they were updated, a
+/// block updated more than once lists every turn.
+pub fn trace_labels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    trace: Res<UpdateTrace>,
+    panel: Res<TracePanel>,
+    labels: Query<Entity, With<TraceLabel>>,
+    mut shown: Local<Option<(Tick, usize)>>,
+) {
+    let latest = trace
+        .latest()
+        .filter(|_| panel.is_open())
+        .map(|latest| (latest.tick, latest.updates.len()));
+    if *shown == latest {
+        return;
+    }
+    *shown = latest;
+
+    for entity in &labels {
+        commands.entity(entity).despawn();
+    }
+    let Some(latest) = trace.latest().filter(|_| panel.is_open()) else {
+        return;
+    };
+
+    let mut turns: Vec<(IVec3, Vec<usize>)> = Vec::new();
+    for (index, update) in latest.updates.iter().enumerate() {
+        match turns
+            .iter()
+            .position(|(position, _)| *position == update.position)
+        {
+            Some(turn) => turns[turn].1.push(index),
+            None if turns.len() < MAX_TRACE_LABELS => turns.push((update.position, vec![index])),
+            None => {}
+        }
+    }
+
+    let fonts: Handle<Font> = asset_server.load("fonts/retro_gaming.ttf");
+    for (position, indices) in turns {
+        let text = indices
+            .iter()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        commands.spawn((
+            Name::new("Trace Label"),
+            Node {
+                position_type: PositionType::Absolute,
+                padding: UiRect::horizontal(px(2)),
+                display: Display::None,
+                ..default()
+            },
+            Text::new(text),
+            TextFont {
+                font: fonts.clone(),
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(YELLOW_400.into()),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            Pickable::IGNORE,
+            WorldLabel(position.as_vec3()),
+            TraceLabel,
+        ));
+    }
+}
+
+/// Keeps each label over its point in the world. The point is projected into the
+/// low-res canvas first and from there onto the window, the canvas is scaled up by
+/// the outer camera.
+pub fn place_world_labels(
+    pixel_camera: Single<(&Camera, &GlobalTransform), With<PixelCamera>>,
+    outer_camera: Single<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    mut labels: Query<(&WorldLabel, &ComputedNode, &mut Node)>,
+) {
+    let (pixel_camera, pixel_transform) = *pixel_camera;
+    let (outer_camera, outer_transform) = *outer_camera;
+    let canvas_size = Vec2::new(RES_WIDTH as f32, RES_HEIGHT as f32);
+
+    for (label, computed, mut node) in &mut labels {
+        let window_position = pixel_camera
+            .world_to_viewport(pixel_transform, label.0)
+            .ok()
+            .map(|texel| (texel - canvas_size / 2.0) * Vec2::new(1.0, -1.0))
+            .and_then(|canvas| {
+                outer_camera
+                    .world_to_viewport(outer_transform, canvas.extend(0.0))
+                    .ok()
+            });
+
+        let Some(window_position) = window_position else {
+            node.display = Display::None;
+            continue;
+        };
+
+        let size = computed.size() * computed.inverse_scale_factor();
+        node.display = Display::Flex;
+        node.left = px(window_position.x - size.x / 2.0);
+        node.top = px(window_position.y - size.y);
+    }
+}
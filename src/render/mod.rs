@@ -6,11 +6,22 @@ use crate::grid_plugin::Grid;
 
 mod debug;
 mod drain;
+mod ghost;
+mod hotbar;
+mod labels;
 mod renderer;
+mod signal_flow;
 
-pub use debug::{debug_info, hovered_block, scheduler_info};
+pub use debug::{
+    backend_info, console_info, debug_info, edition_info, hovered_block, inspector_info, loop_info,
+    save_list, scheduler_info, schematic_info, selection_info, timeline_info, trace_info,
+};
 pub use drain::cleanup;
+pub use ghost::{block_meshes, preview_placement};
+pub use hotbar::hotbar_info;
+pub use labels::{PowerLabels, place_world_labels, power_labels, trace_labels};
 pub use renderer::renderer;
+pub use signal_flow::{SignalFlow, signal_flow};
 
 #[derive(Component, Debug, Clone, Copy)]
 pub struct Position(pub IVec3);
@@ -86,6 +97,8 @@ pub struct RenderPlugin;
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut bevy::app::App) {
         app.init_resource::<BlockEntities>()
-            .init_resource::<DirtyBlocks>();
+            .init_resource::<DirtyBlocks>()
+            .init_resource::<PowerLabels>()
+            .init_resource::<SignalFlow>();
     }
 }
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::{
+    color::palettes::tailwind::{ORANGE_400, YELLOW_400},
+    prelude::*,
+};
+
+use crate::{
+    blocks::{BlockType, Tickable},
+    chunk::Chunk,
+    grid_plugin::{Grid, UpdateSource, UpdateTrace},
+    redstone::Tick,
+};
+
+/// Trips per second a pulse makes from one dust to the next.
+const PULSE_SPEED: f32 = 1.5;
+/// Seconds between two flashes, so the update order can be followed by eye.
+const FLASH_STEP: f32 = 0.05;
+const FLASH_TIME: f32 = 0.3;
+/// Flashes waiting at most, a tick with more updates than this only shows the first.
+const MAX_FLASHES: usize = 256;
+
+/// Animates pulses along dust towards lower power and flashes blocks in the order
+/// the scheduler ran their updates.
+#[derive(Resource, Default)]
+pub struct SignalFlow {
+    enabled: bool,
+    /// Positions with the time they light up.
+    flashes: VecDeque<(IVec3, f32)>,
+    next_flash: f32,
+    /// Tick and number of updates already queued up for flashing.
+    seen: (Tick, usize),
+}
+
+impl SignalFlow {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Line a pulse travels along, from the brighter dust to the dimmer one.
+type PulsePath = (Vec3, Vec3);
+
+/// Pulse paths kept per chunk along with the chunk revision they were found in, so
+/// only chunks that changed, and their neighbours that look into them, are walked again.
+#[derive(Default)]
+pub struct PulsePaths {
+    revision: Option<u64>,
+    chunks: HashMap<IVec3, (Option<u64>, Vec<PulsePath>)>,
+}
+
+impl PulsePaths {
+    fn refresh(&mut self, grid: &Grid) {
+        if self.revision == Some(grid.revision()) {
+            return;
+        }
+        self.revision = Some(grid.revision());
+
+        let current: HashMap<IVec3, &Chunk> = grid
+            .chunks()
+            .map(|(coords, chunk)| (*coords, chunk))
+            .collect();
+        let changed = current
+            .keys()
+            .filter(|coords| {
+                self.chunks.get(coords).map(|(revision, _)| *revision)
+                    != Some(grid.chunk_revision(**coords))
+            })
+            .chain(
+                self.chunks
+                    .keys()
+                    .filter(|coords| !current.contains_key(coords)),
+            );
+
+        // Paths reach one block into the chunks around, so those are walked again too
+        let mut stale = HashSet::new();
+        for coords in changed {
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    for z in -1..=1 {
+                        stale.insert(*coords + IVec3::new(x, y, z));
+                    }
+                }
+            }
+        }
+
+        for coords in stale {
+            match current.get(&coords) {
+                Some(chunk) => {
+                    let paths = pulse_paths(grid, chunk);
+                    self.chunks
+                        .insert(coords, (grid.chunk_revision(coords), paths));
+                }
+                None => {
+                    self.chunks.remove(&coords);
+                }
+            }
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &PulsePath> {
+        self.chunks.values().flat_map(|(_, paths)| paths)
+    }
+}
+
+/// Where a pulse starting in `chunk` travels between, the lines are drawn just above
+/// the floor the dust lies on, stepping up or down with it.
+fn pulse_paths(grid: &Grid, chunk: &Chunk) -> Vec<PulsePath> {
+    let floor = |position: IVec3| position.as_vec3() - Vec3::Y * 0.45;
+    let mut paths = Vec::new();
+
+    for (position, data) in chunk.iter() {
+        let BlockType::Dust(dust) = data.block_type else {
+            continue;
+        };
+        if dust.power() == 0 {
+            continue;
+        }
+
+        for side in dust.shape.sides() {
+            for step in [IVec3::ZERO, IVec3::Y, IVec3::NEG_Y] {
+                let next = position + side + step;
+                if let Some(BlockType::Dust(other)) = grid.get_blocktype(next)
+                    && other.power() < dust.power()
+                {
+                    paths.push((floor(position), floor(next)));
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+pub fn signal_flow(
+    time: Res<Time>,
+    grid: Res<Grid>,
+    trace: Res<UpdateTrace>,
+    mut flow: ResMut<SignalFlow>,
+    mut gizmos: Gizmos,
+    mut paths: Local<PulsePaths>,
+) {
+    let (tick, queued) = flow.seen;
+    let (latest, updates) = trace
+        .latest()
+        .map_or((tick, &[][..]), |latest| (latest.tick, &latest.updates[..]));
+    let new = if latest == tick {
+        &updates[queued.min(updates.len())..]
+    } else {
+        updates
+    };
+    flow.seen = (latest, updates.len());
+
+    if !flow.is_enabled() {
+        flow.flashes.clear();
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    let mut at = flow.next_flash.max(now);
+    let room = MAX_FLASHES.saturating_sub(flow.flashes.len());
+    for update in new
+        .iter()
+        .filter(|update| update.source == UpdateSource::Immediate)
+        .take(room)
+    {
+        flow.flashes.push_back((update.position, at));
+        at += FLASH_STEP;
+    }
+    flow.next_flash = at;
+
+    flow.flashes.retain(|(_, at)| now < at + FLASH_TIME);
+    for (position, at) in &flow.flashes {
+        if now < *at {
+            break;
+        }
+        let fade = 1.0 - (now - at) / FLASH_TIME;
+        gizmos.cuboid(
+            Transform::from_translation(position.as_vec3()).with_scale(Vec3::splat(1.02)),
+            Color::from(YELLOW_400).with_alpha(fade),
+        );
+    }
+
+    paths.refresh(&grid);
+    let progress = (now * PULSE_SPEED).fract();
+    for (from, to) in paths.iter() {
+        gizmos.cuboid(
+            Transform::from_translation(from.lerp(*to, progress)).with_scale(Vec3::splat(0.12)),
+            ORANGE_400,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocks::Dust, chunk::CHUNK_SIZE, redstone::junctions::JunctionType};
+
+    fn sorted(paths: impl IntoIterator<Item = PulsePath>) -> Vec<[i32; 6]> {
+        let mut paths: Vec<_> = paths
+            .into_iter()
+            .map(|(from, to)| {
+                let (from, to) = ((from * 100.0).as_ivec3(), (to * 100.0).as_ivec3());
+                [from.x, from.y, from.z, to.x, to.y, to.z]
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn dust(power: u8) -> Option<BlockType> {
+        let mut dust = Dust::default();
+        dust.shape = JunctionType::Cross;
+        Some(BlockType::Dust(dust.with_power(power)))
+    }
+
+    fn full(grid: &Grid) -> Vec<[i32; 6]> {
+        sorted(
+            grid.chunks()
+                .flat_map(|(_, chunk)| pulse_paths(grid, chunk)),
+        )
+    }
+
+    #[test]
+    fn refreshing_changed_chunks_matches_a_full_walk() {
+        let mut grid = Grid::default();
+        let mut paths = PulsePaths::default();
+        // A line of dust fading out across the border between two chunks
+        let edge = CHUNK_SIZE - 3;
+        for x in 0..6 {
+            grid.set(IVec3::new(edge + x, 0, 0), dust(15 - x as u8));
+        }
+        paths.refresh(&grid);
+        assert_eq!(sorted(paths.iter().copied()), full(&grid));
+        assert!(!full(&grid).is_empty());
+
+        // Powering the dust just past the border turns the path that leads into it
+        grid.set(IVec3::new(CHUNK_SIZE, 0, 0), dust(15));
+        paths.refresh(&grid);
+        assert_eq!(sorted(paths.iter().copied()), full(&grid));
+
+        // Emptying the far chunk drops its paths and the ones leading into it
+        for x in CHUNK_SIZE..edge + 6 {
+            grid.set(IVec3::new(x, 0, 0), None);
+        }
+        paths.refresh(&grid);
+        assert_eq!(sorted(paths.iter().copied()), full(&grid));
+    }
+}
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blocks::{
+        BlockType, Dirt, Dropper, Dust, RedStone, RedStoneLamp, RedStoneTorch, StandardGrass,
+        Tickable,
+    },
+    redstone::{
+        TickPriority,
+        junctions::JunctionType,
+        rules::{Edition, SimulationRules},
+    },
+};
+
+/// Bump whenever the layout below changes, and teach [`super::migrate`] how to bring
+/// the previous version up to date.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Read on its own first, so the rest of the file can be parsed as the version it was
+/// written with.
+#[derive(Deserialize)]
+pub struct Header {
+    pub version: u32,
+}
+
+/// The on-disk layout. Deliberately separate from the runtime types, so changing
+/// `BlockType` doesn't silently change what old files mean.
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub tick: u64,
+    pub rules: SavedRules,
+    pub blocks: Vec<SavedBlock>,
+    /// Pending tile ticks in the order they were scheduled.
+    pub scheduled: Vec<SavedTick>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedRules {
+    pub bedrock: bool,
+    pub quasi_connectivity: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedBlock {
+    pub position: [i32; 3],
+    pub block: SavedBlockType,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum SavedBlockType {
+    StandardGrass,
+    Dirt,
+    RedStone,
+    RedStoneLamp { power: u8 },
+    RedStoneTorch { lit: bool, attached_face: [i32; 3] },
+    Dust { shape: String, power: u8 },
+    Dropper { facing: [i32; 3], triggered: bool },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedTick {
+    pub tick: u64,
+    pub position: [i32; 3],
+    pub priority: i8,
+}
+
+const SHAPES: [(JunctionType, &str); 12] = [
+    (JunctionType::Dot, "dot"),
+    (JunctionType::Vertical, "vertical"),
+    (JunctionType::Horizontal, "horizontal"),
+    (JunctionType::CornerNE, "corner_ne"),
+    (JunctionType::CornerNW, "corner_nw"),
+    (JunctionType::CornerSE, "corner_se"),
+    (JunctionType::CornerSW, "corner_sw"),
+    (JunctionType::TNorth, "t_north"),
+    (JunctionType::TSouth, "t_south"),
+    (JunctionType::TEast, "t_east"),
+    (JunctionType::TWest, "t_west"),
+    (JunctionType::Cross, "cross"),
+];
+
+const PRIORITIES: [TickPriority; 7] = [
+    TickPriority::ExtremelyHigh,
+    TickPriority::VeryHigh,
+    TickPriority::High,
+    TickPriority::Normal,
+    TickPriority::Low,
+    TickPriority::VeryLow,
+    TickPriority::ExtremelyLow,
+];
+
+/// Java Edition numbers priorities from -3 (extremely high) to 3 (extremely low).
+pub fn priority_to_saved(priority: TickPriority) -> i8 {
+    PRIORITIES.iter().position(|p| *p == priority).unwrap_or(3) as i8 - 3
+}
+
+pub fn priority_from_saved(priority: i8) -> TickPriority {
+    PRIORITIES
+        .get((priority + 3).clamp(0, 6) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+impl From<&SimulationRules> for SavedRules {
+    fn from(rules: &SimulationRules) -> Self {
+        Self {
+            bedrock: rules.edition == Edition::Bedrock,
+            quasi_connectivity: rules.quasi_connectivity,
+        }
+    }
+}
+
+impl From<&SavedRules> for SimulationRules {
+    fn from(rules: &SavedRules) -> Self {
+        Self {
+            edition: if rules.bedrock {
+                Edition::Bedrock
+            } else {
+                Edition::Java
+            },
+            quasi_connectivity: rules.quasi_connectivity,
+        }
+    }
+}
+
+impl SavedBlockType {
+    /// `None` for blocks that are never stored, like air.
+    pub fn from_block_type(block_type: &BlockType) -> Option<Self> {
+        let saved = match block_type {
+            BlockType::Air => return None,
+            BlockType::StandardGrass(_) => Self::StandardGrass,
+            BlockType::Dirt(_) => Self::Dirt,
+            BlockType::RedStone(_) => Self::RedStone,
+            BlockType::RedStoneLamp(lamp) => Self::RedStoneLamp {
+                power: lamp.power(),
+            },
+            BlockType::RedStoneTorch(torch) => Self::RedStoneTorch {
+                lit: torch.lit,
+                attached_face: torch.attached_face.to_array(),
+            },
+            BlockType::Dust(dust) => Self::Dust {
+                shape: SHAPES
+                    .iter()
+                    .find(|(shape, _)| *shape == dust.shape)
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or_default(),
+                power: dust.power(),
+            },
+            BlockType::Dropper(dropper) => Self::Dropper {
+                facing: dropper.facing.to_array(),
+                triggered: dropper.triggered,
+            },
+        };
+        Some(saved)
+    }
+
+    /// Unknown dust shapes fall back to a dot, the shape is recomputed on the next
+    /// neighbour update anyway.
+    pub fn to_block_type(&self) -> BlockType {
+        match self {
+            Self::StandardGrass => BlockType::StandardGrass(StandardGrass),
+            Self::Dirt => BlockType::Dirt(Dirt),
+            Self::RedStone => BlockType::RedStone(RedStone),
+            Self::RedStoneLamp { power } => {
+                BlockType::RedStoneLamp(RedStoneLamp::default().with_power(*power))
+            }
+            Self::RedStoneTorch { lit, attached_face } => BlockType::RedStoneTorch(RedStoneTorch {
+                lit: *lit,
+                attached_face: IVec3::from_array(*attached_face),
+            }),
+            Self::Dust { shape, power } => {
+                let shape = SHAPES
+                    .iter()
+                    .find(|(_, name)| name == shape)
+                    .map(|(shape, _)| *shape)
+                    .unwrap_or_default();
+                let mut dust = Dust::default().with_power(*power);
+                dust.shape = shape;
+                BlockType::Dust(dust)
+            }
+            Self::Dropper { facing, triggered } => BlockType::Dropper(Dropper {
+                facing: IVec3::from_array(*facing),
+                triggered: *triggered,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        grid_plugin::Grid,
+        redstone::{GlobalTick, Scheduler},
+        save::{SaveError, parse_save, snapshot},
+    };
+
+    /// One of every block kind the format knows, dust in every shape.
+    fn every_block() -> Vec<BlockType> {
+        let mut blocks = vec![
+            BlockType::StandardGrass(StandardGrass),
+            BlockType::Dirt(Dirt),
+            BlockType::RedStone(RedStone),
+            BlockType::RedStoneLamp(RedStoneLamp::default().with_power(9)),
+            BlockType::RedStoneTorch(RedStoneTorch {
+                lit: true,
+                attached_face: IVec3::NEG_Z,
+            }),
+            BlockType::RedStoneTorch(RedStoneTorch {
+                lit: false,
+                attached_face: IVec3::Y,
+            }),
+            BlockType::Dropper(Dropper {
+                facing: IVec3::NEG_X,
+                triggered: true,
+            }),
+        ];
+        for (power, (shape, _)) in SHAPES.iter().enumerate() {
+            let mut dust = Dust::default().with_power(power as u8);
+            dust.shape = *shape;
+            blocks.push(BlockType::Dust(dust));
+        }
+        blocks
+    }
+
+    fn round_trip(save: &SaveFile) -> SaveFile {
+        let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default())
+            .expect("save serializes");
+        parse_save(&text).expect("save parses")
+    }
+
+    #[test]
+    fn saved_world_loads_back_unchanged() {
+        let blocks = every_block();
+        let mut grid = Grid::default();
+        for (x, block_type) in blocks.iter().enumerate() {
+            grid.set(IVec3::new(x as i32, 0, -3), Some(*block_type));
+        }
+
+        let mut scheduler = Scheduler::default();
+        for (x, priority) in PRIORITIES.iter().enumerate() {
+            scheduler.schedule_at(IVec3::new(x as i32, 0, -3), 120 + x as u64 % 3, *priority);
+        }
+
+        let mut tick_counter = GlobalTick::default();
+        tick_counter.set(117);
+
+        for rules in [
+            SimulationRules {
+                edition: Edition::Java,
+                quasi_connectivity: true,
+            },
+            SimulationRules {
+                edition: Edition::Java,
+                quasi_connectivity: false,
+            },
+            SimulationRules {
+                edition: Edition::Bedrock,
+                quasi_connectivity: false,
+            },
+        ] {
+            *grid.rules_mut() = rules;
+            let loaded = round_trip(&snapshot(&grid, &scheduler, &tick_counter));
+
+            assert_eq!(loaded.version, CURRENT_VERSION);
+            assert_eq!(loaded.tick, 117);
+            assert_eq!(SimulationRules::from(&loaded.rules), rules);
+
+            assert_eq!(loaded.blocks.len(), blocks.len());
+            for block in &loaded.blocks {
+                let position = IVec3::from_array(block.position);
+                assert_eq!(
+                    Some(&block.block.to_block_type()),
+                    grid.get_blocktype(position),
+                    "at {}",
+                    position
+                );
+            }
+
+            let scheduled: Vec<_> = loaded
+                .scheduled
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.tick,
+                        IVec3::from_array(entry.position),
+                        priority_from_saved(entry.priority),
+                    )
+                })
+                .collect();
+            assert_eq!(scheduled, scheduler.pending(117));
+        }
+    }
+
+    #[test]
+    fn priorities_keep_java_numbering() {
+        assert_eq!(priority_to_saved(TickPriority::ExtremelyHigh), -3);
+        assert_eq!(priority_to_saved(TickPriority::Normal), 0);
+        assert_eq!(priority_to_saved(TickPriority::ExtremelyLow), 3);
+        for priority in PRIORITIES {
+            assert_eq!(priority_from_saved(priority_to_saved(priority)), priority);
+        }
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let text = "(version: 99, tick: 0, rules: (bedrock: false, quasi_connectivity: true), \
+                    blocks: [], scheduled: [])";
+        assert!(matches!(
+            parse_save(text),
+            Err(SaveError::UnsupportedVersion(99))
+        ));
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    GameLoop,
+    bindings::{Action, Actions},
+    grid_plugin::{BlockChangeQueue, Grid},
+    history_plugin::is_live,
+    interactions::EditHistory,
+    redstone::{GlobalTick, Scheduler},
+    render::{BlockEntities, DirtyBlocks, DirtyRender},
+};
+
+mod format;
+
+use format::{
+    CURRENT_VERSION, Header, SaveFile, SavedBlock, SavedBlockType, SavedTick, priority_from_saved,
+    priority_to_saved,
+};
+
+const SAVE_DIR: &str = "saves";
+const EXTENSION: &str = "ron";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Format(err) => write!(f, "malformed save file: {}", err),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save file version {} is not supported, newest known is {}",
+                version, CURRENT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+/// Save files found in the save directory, newest last.
+#[derive(Resource, Default)]
+pub struct SaveSlots {
+    files: Vec<PathBuf>,
+    selected: usize,
+}
+
+impl SaveSlots {
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn selected(&self) -> Option<&PathBuf> {
+        self.files.get(self.selected)
+    }
+
+    fn refresh(&mut self) {
+        self.files = fs::read_dir(SAVE_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.files.sort();
+        self.selected = self.selected.min(self.files.len().saturating_sub(1));
+    }
+
+    fn select(&mut self, path: &Path) {
+        if let Some(index) = self.files.iter().position(|file| file == path) {
+            self.selected = index;
+        }
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveSlots>()
+            .add_systems(Startup, refresh_save_slots)
+            .add_systems(
+                Update,
+                (save_world, cycle_save_slots, load_world)
+                    .chain()
+                    .in_set(GameLoop::Input)
+                    .run_if(is_live),
+            );
+    }
+}
+
+fn snapshot(grid: &Grid, scheduler: &Scheduler, tick_counter: &GlobalTick) -> SaveFile {
+    let mut blocks: Vec<SavedBlock> = grid
+        .iter()
+        .filter_map(|(position, data)| {
+            SavedBlockType::from_block_type(&data.block_type).map(|block| SavedBlock {
+                position: position.to_array(),
+                block,
+            })
+        })
+        .collect();
+    blocks.sort_by_key(|block| (block.position[1], block.position[2], block.position[0]));
+
+    let scheduled = scheduler
+        .pending(tick_counter.read())
+        .into_iter()
+        .map(|(tick, position, priority)| SavedTick {
+            tick,
+            position: position.to_array(),
+            priority: priority_to_saved(priority),
+        })
+        .collect();
+
+    SaveFile {
+        version: CURRENT_VERSION,
+        tick: tick_counter.read(),
+        rules: grid.rules().into(),
+        blocks,
+        scheduled,
+    }
+}
+
+pub fn write_save(path: &Path, save: &SaveFile) -> Result<(), SaveError> {
+    let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default())
+        .map_err(|err| SaveError::Format(err.to_string()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn read_save(path: &Path) -> Result<SaveFile, SaveError> {
+    parse_save(&fs::read_to_string(path)?)
+}
+
+fn parse_save(text: &str) -> Result<SaveFile, SaveError> {
+    let header: Header = ron::from_str(text).map_err(|err| SaveError::Format(err.to_string()))?;
+    migrate(header.version, text)
+}
+
+/// Parses a file with the layout of its own version and converts it to the current
+/// one. When the format changes, keep the old layout in its own module and add a
+/// conversion here, e.g. `1 => Ok(ron::from_str::<v1::SaveFile>(text)?.into())`.
+fn migrate(version: u32, text: &str) -> Result<SaveFile, SaveError> {
+    match version {
+        CURRENT_VERSION => ron::from_str(text).map_err(|err| SaveError::Format(err.to_string())),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+fn refresh_save_slots(mut slots: ResMut<SaveSlots>) {
+    slots.refresh();
+}
+
+fn save_world(
+    actions: Actions,
+    mut slots: ResMut<SaveSlots>,
+    grid: Res<Grid>,
+    scheduler: Res<Scheduler>,
+    tick_counter: Res<GlobalTick>,
+) {
+    if !actions.just_pressed(Action::Save) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = Path::new(SAVE_DIR).join(format!("world-{}.{}", timestamp, EXTENSION));
+
+    match write_save(&path, &snapshot(&grid, &scheduler, &tick_counter)) {
+        Ok(()) => {
+            info!("Saved world to {}", path.display());
+            slots.refresh();
+            slots.select(&path);
+        }
+        Err(err) => warn!("Could not save {}: {}", path.display(), err),
+    }
+}
+
+fn cycle_save_slots(actions: Actions, mut slots: ResMut<SaveSlots>) {
+    if slots.files.is_empty() {
+        return;
+    }
+
+    if actions.just_pressed(Action::PreviousSave) {
+        slots.selected = slots
+            .selected
+            .checked_sub(1)
+            .unwrap_or(slots.files.len() - 1);
+    }
+
+    if actions.just_pressed(Action::NextSave) {
+        slots.selected = (slots.selected + 1) % slots.files.len();
+    }
+}
+
+/// Everything loading a save replaces.
+#[derive(SystemParam)]
+struct LoadedWorld<'w> {
+    grid: ResMut<'w, Grid>,
+    scheduler: ResMut<'w, Scheduler>,
+    tick_counter: ResMut<'w, GlobalTick>,
+    queue: ResMut<'w, BlockChangeQueue>,
+    edits: ResMut<'w, EditHistory>,
+}
+
+fn load_world(
+    actions: Actions,
+    mut commands: Commands,
+    slots: Res<SaveSlots>,
+    mut world: LoadedWorld,
+    mut block_entities: ResMut<BlockEntities>,
+    mut dirty_blocks: ResMut<DirtyBlocks>,
+    mut dirty_render: ResMut<DirtyRender>,
+) {
+    if !actions.just_pressed(Action::Load) {
+        return;
+    }
+
+    let Some(path) = slots.selected() else {
+        info!("No save file to load");
+        return;
+    };
+
+    let save = match read_save(path) {
+        Ok(save) => save,
+        Err(err) => {
+            warn!("Could not load {}: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let existing: Vec<IVec3> = world.grid.iter().map(|(position, _)| position).collect();
+    for position in existing {
+        world.grid.remove(position);
+    }
+
+    for block in &save.blocks {
+        let position = IVec3::from_array(block.position);
+        world.grid.set(position, Some(block.block.to_block_type()));
+        dirty_render.mark(position);
+    }
+    *world.grid.rules_mut() = (&save.rules).into();
+
+    *world.scheduler = Scheduler::default();
+    for entry in &save.scheduled {
+        world.scheduler.schedule_at(
+            IVec3::from_array(entry.position),
+            entry.tick,
+            priority_from_saved(entry.priority),
+        );
+    }
+
+    world.tick_counter.stop();
+    world.tick_counter.set(save.tick);
+
+    // Everything is respawned from scratch, blocks may have changed kind in place
+    for (_, entity) in block_entities.entities.drain() {
+        commands.entity(entity).despawn();
+    }
+    dirty_blocks.positions.clear();
+    // Edits and input from before the load refer to blocks that are gone
+    world.queue.clear();
+    *world.edits = EditHistory::default();
+
+    info!(
+        "Loaded {} blocks from {}",
+        save.blocks.len(),
+        path.display()
+    );
+}
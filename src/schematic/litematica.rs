@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+
+use crate::schematic::{DATA_VERSION, Schematic, SchematicError, nbt::Tag, states::BlockState};
+
+fn vec3(tag: Option<&Tag>, name: &str) -> Result<IVec3, SchematicError> {
+    let tag = tag.ok_or_else(|| SchematicError::Malformed(format!("missing {}", name)))?;
+    let component = |axis: &str| {
+        tag.get(axis)
+            .and_then(Tag::as_int)
+            .map(|value| value as i32)
+            .ok_or_else(|| SchematicError::Malformed(format!("missing {}.{}", name, axis)))
+    };
+    Ok(IVec3::new(
+        component("x")?,
+        component("y")?,
+        component("z")?,
+    ))
+}
+
+fn palette_entry(tag: &Tag) -> BlockState {
+    BlockState {
+        name: tag
+            .get("Name")
+            .and_then(Tag::as_str)
+            .unwrap_or("minecraft:air")
+            .to_string(),
+        properties: tag
+            .get("Properties")
+            .map(|properties| {
+                properties
+                    .entries()
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Litematica packs palette indices tightly, an entry may straddle two longs.
+fn read_packed(longs: &[i64], bits: u32, index: usize) -> Option<usize> {
+    let mask = (1u64 << bits) - 1;
+    let start_bit = index * bits as usize;
+    let start_long = start_bit / 64;
+    let offset = (start_bit % 64) as u32;
+    let end_long = (start_bit + bits as usize - 1) / 64;
+
+    let low = *longs.get(start_long)? as u64 >> offset;
+    let value = if start_long == end_long {
+        low
+    } else {
+        low | (*longs.get(end_long)? as u64) << (64 - offset)
+    };
+    Some((value & mask) as usize)
+}
+
+/// Every region of a `.litematic`, positioned relative to each other.
+pub fn parse(root: &Tag) -> Result<Schematic, SchematicError> {
+    let regions = root
+        .get("Regions")
+        .ok_or_else(|| SchematicError::Malformed("missing Regions".to_string()))?;
+
+    let mut blocks = Vec::new();
+    for (name, region) in regions.entries() {
+        let position = vec3(region.get("Position"), "Position")?;
+        let size = vec3(region.get("Size"), "Size")?;
+        let too_large = || SchematicError::Malformed(format!("region {} is too large", name));
+
+        // Negative sizes extend the region backwards from its position
+        let min = position
+            .checked_add(size.map(|s| if s < 0 { s + 1 } else { 0 }))
+            .ok_or_else(too_large)?;
+        let [Some(x), Some(y), Some(z)] = size.to_array().map(i32::checked_abs) else {
+            return Err(too_large());
+        };
+        let dims = [x as usize, y as usize, z as usize];
+        let volume = dims
+            .into_iter()
+            .try_fold(1usize, |volume, axis| volume.checked_mul(axis))
+            .ok_or_else(too_large)?;
+
+        let palette: Vec<BlockState> = region
+            .get("BlockStatePalette")
+            .map(|palette| palette.elements().iter().map(palette_entry).collect())
+            .unwrap_or_default();
+        let states = region
+            .get("BlockStates")
+            .and_then(Tag::as_longs)
+            .ok_or_else(|| SchematicError::Malformed(format!("region {} has no blocks", name)))?;
+
+        let bits = (usize::BITS - palette.len().saturating_sub(1).leading_zeros()).max(2);
+        for index in 0..volume {
+            let id = read_packed(states, bits, index).ok_or_else(|| {
+                SchematicError::Malformed(format!("region {} is truncated", name))
+            })?;
+            let Some(state) = palette.get(id) else {
+                return Err(SchematicError::Malformed(format!(
+                    "unknown palette id {} in region {}",
+                    id, name
+                )));
+            };
+
+            let offset = IVec3::new(
+                (index % dims[0]) as i32,
+                (index / (dims[0] * dims[2])) as i32,
+                ((index / dims[0]) % dims[2]) as i32,
+            );
+            let position = min.checked_add(offset).ok_or_else(too_large)?;
+            blocks.push((position, state.clone()));
+        }
+    }
+
+    let Some(origin) = blocks
+        .iter()
+        .map(|(position, _)| *position)
+        .reduce(IVec3::min)
+    else {
+        return Err(SchematicError::Malformed("no regions".to_string()));
+    };
+    let max = blocks
+        .iter()
+        .map(|(position, _)| *position)
+        .fold(origin, IVec3::max);
+
+    for (position, _) in &mut blocks {
+        *position -= origin;
+    }
+
+    let size = max
+        .checked_sub(origin)
+        .and_then(|extent| extent.checked_add(IVec3::ONE))
+        .ok_or_else(|| SchematicError::Malformed("regions are too far apart".to_string()))?;
+
+    Ok(Schematic {
+        size,
+        data_version: root
+            .get("MinecraftDataVersion")
+            .and_then(Tag::as_int)
+            .map_or(DATA_VERSION, |version| version as i32),
+        blocks,
+        source: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schematic::nbt::COMPOUND;
+
+    fn vec3_tag(value: IVec3) -> Tag {
+        Tag::Compound(vec![
+            ("x".to_string(), Tag::Int(value.x)),
+            ("y".to_string(), Tag::Int(value.y)),
+            ("z".to_string(), Tag::Int(value.z)),
+        ])
+    }
+
+    fn litematic(position: IVec3, size: IVec3, states: Vec<i64>) -> Tag {
+        let state =
+            |name: &str| Tag::Compound(vec![("Name".to_string(), Tag::String(name.to_string()))]);
+        Tag::Compound(vec![(
+            "Regions".to_string(),
+            Tag::Compound(vec![(
+                "main".to_string(),
+                Tag::Compound(vec![
+                    ("Position".to_string(), vec3_tag(position)),
+                    ("Size".to_string(), vec3_tag(size)),
+                    (
+                        "BlockStatePalette".to_string(),
+                        Tag::List(
+                            COMPOUND,
+                            vec![state("minecraft:air"), state("minecraft:redstone_block")],
+                        ),
+                    ),
+                    ("BlockStates".to_string(), Tag::LongArray(states)),
+                ]),
+            )]),
+        )])
+    }
+
+    #[test]
+    fn negative_sizes_extend_backwards() {
+        // Two bits per entry, redstone at index 1
+        let root = litematic(IVec3::new(5, 0, 0), IVec3::new(-2, 1, 1), vec![0b0100]);
+        let parsed = parse(&root).unwrap();
+
+        assert_eq!(parsed.size, IVec3::new(2, 1, 1));
+        assert_eq!(parsed.blocks[1].0, IVec3::new(1, 0, 0));
+        assert_eq!(parsed.blocks[1].1.name, "minecraft:redstone_block");
+    }
+
+    #[test]
+    fn rejects_truncated_block_states() {
+        let root = litematic(IVec3::ZERO, IVec3::new(64, 1, 1), vec![0]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+
+    #[test]
+    fn rejects_sizes_without_an_absolute_value() {
+        let root = litematic(IVec3::ZERO, IVec3::new(i32::MIN, 1, 1), vec![0]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+
+    #[test]
+    fn rejects_oversized_volumes() {
+        let root = litematic(IVec3::ZERO, IVec3::splat(i32::MAX), vec![0]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+
+        let root = litematic(IVec3::splat(i32::MAX), IVec3::new(2, 1, 1), vec![0]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+}
@@ -0,0 +1,456 @@
+use bevy::{prelude::*, window::FileDragAndDrop};
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    GameLoop,
+    bindings::{Action, Actions},
+    blocks::{BlockType, NeighbourUpdate},
+    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place},
+    history_plugin::is_live,
+    interactions::HoveredBlockInfo,
+};
+
+mod litematica;
+mod nbt;
+mod sponge;
+mod states;
+
+use nbt::Tag;
+use states::from_block_type;
+pub use states::{BlockState, MappedState, to_block_type};
+
+/// Minecraft 1.21, written when the world did not come from a schematic.
+pub const DATA_VERSION: i32 = 3953;
+
+const EXPORT_DIR: &str = "schematics";
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(io::Error),
+    Malformed(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Io(err) => write!(f, "{}", err),
+            SchematicError::Malformed(err) => write!(f, "malformed schematic: {}", err),
+            SchematicError::Unsupported(err) => write!(f, "unsupported schematic: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for SchematicError {
+    fn from(err: io::Error) -> Self {
+        SchematicError::Io(err)
+    }
+}
+
+/// Blocks of a schematic, positioned relative to its minimum corner.
+#[derive(Debug)]
+pub struct Schematic {
+    pub size: IVec3,
+    pub data_version: i32,
+    pub blocks: Vec<(IVec3, BlockState)>,
+    /// The Sponge v3 compound this was read from, written back on export.
+    source: Option<Tag>,
+}
+
+impl Schematic {
+    /// Every block in the area, blocks without a Minecraft equivalent become air and
+    /// are counted by name.
+    pub fn from_grid(
+        grid: &Grid,
+        min: IVec3,
+        size: IVec3,
+    ) -> (Self, BTreeMap<&'static str, usize>) {
+        let mut skipped = BTreeMap::new();
+        let mut blocks = Vec::new();
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    let offset = IVec3::new(x, y, z);
+                    let block_type = grid
+                        .get(min + offset)
+                        .map_or(BlockType::Air, |data| data.block_type);
+                    match from_block_type(&block_type) {
+                        Some(state) => blocks.push((offset, state)),
+                        None => *skipped.entry(block_name(&block_type)).or_default() += 1,
+                    }
+                }
+            }
+        }
+
+        let schematic = Self {
+            size,
+            data_version: DATA_VERSION,
+            blocks,
+            source: None,
+        };
+        (schematic, skipped)
+    }
+}
+
+fn block_name(block_type: &BlockType) -> &'static str {
+    match block_type {
+        BlockType::Air => "air",
+        BlockType::StandardGrass(_) => "grass",
+        BlockType::Dirt(_) => "dirt",
+        BlockType::RedStone(_) => "redstone block",
+        BlockType::RedStoneLamp(_) => "lamp",
+        BlockType::RedStoneTorch(_) => "torch",
+        BlockType::Dust(_) => "dust",
+        BlockType::Dropper(_) => "dropper",
+    }
+}
+
+pub fn read_schematic(path: &Path) -> Result<Schematic, SchematicError> {
+    let bytes = fs::read(path)?;
+    let (_, root) = nbt::read_compressed(&bytes)?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("schem") => sponge::parse(&root),
+        Some("litematic") => litematica::parse(&root),
+        _ => Err(SchematicError::Unsupported(format!(
+            "{} is not a .schem or .litematic file",
+            path.display()
+        ))),
+    }
+}
+
+/// Always written as a Sponge v3 `.schem`.
+pub fn write_schematic(path: &Path, schematic: &Schematic) -> Result<(), SchematicError> {
+    let bytes = nbt::write_compressed("", &sponge::write(schematic))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Outcome of the latest import or export, shown in the debug view.
+#[derive(Resource, Default)]
+pub struct SchematicReport {
+    pub message: String,
+}
+
+/// The area an export writes, the last imported schematic until something else picks
+/// one. Without one the whole world is exported.
+#[derive(Resource, Default)]
+pub struct ExportRegion {
+    area: Option<(IVec3, IVec3)>,
+    data_version: Option<i32>,
+    source: Option<Tag>,
+}
+
+impl ExportRegion {
+    pub fn set(&mut self, min: IVec3, size: IVec3) {
+        *self = Self {
+            area: Some((min, size)),
+            ..default()
+        };
+    }
+
+    fn set_imported(&mut self, min: IVec3, schematic: &Schematic) {
+        *self = Self {
+            area: Some((min, schematic.size)),
+            data_version: Some(schematic.data_version),
+            source: schematic.source.clone(),
+        };
+    }
+}
+
+pub struct SchematicPlugin;
+
+impl Plugin for SchematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SchematicReport>()
+            .init_resource::<ExportRegion>()
+            .add_systems(
+                Update,
+                (import_dropped_schematics, export_schematic)
+                    .in_set(GameLoop::Input)
+                    .run_if(is_live),
+            );
+    }
+}
+
+/// Files dropped on the window are placed on top of the hovered block, or at the
+/// origin when nothing is hovered.
+fn import_dropped_schematics(
+    mut drops: MessageReader<FileDragAndDrop>,
+    hovered: Res<HoveredBlockInfo>,
+    mut queue: ResMut<BlockChangeQueue>,
+    mut region: ResMut<ExportRegion>,
+    mut report: ResMut<SchematicReport>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+
+        let schematic = match read_schematic(path_buf) {
+            Ok(schematic) => schematic,
+            Err(err) => {
+                warn!("Could not import {}: {}", path_buf.display(), err);
+                report.message = format!("Failed: {}", err);
+                continue;
+            }
+        };
+
+        let origin = match (hovered.position, hovered.normal) {
+            (Some(position), Some(normal)) => position + normal,
+            _ => IVec3::ZERO,
+        };
+
+        let mut placed = 0;
+        let mut unsupported: BTreeMap<&str, usize> = BTreeMap::new();
+        for (position, state) in &schematic.blocks {
+            match to_block_type(state) {
+                MappedState::Air => {}
+                MappedState::Block(block_type) => {
+                    queue.push_input(BlockChange::Place(Place::new(
+                        Some(block_type),
+                        origin + *position,
+                        true,
+                        None,
+                        NeighbourUpdate::EXTENDED.to_vec(),
+                    )));
+                    placed += 1;
+                }
+                MappedState::Unsupported => *unsupported.entry(&state.name).or_default() += 1,
+            }
+        }
+
+        region.set_imported(origin, &schematic);
+
+        let name = path_buf.file_name().unwrap_or_default().to_string_lossy();
+        info!("Imported {} blocks from {}", placed, name);
+        report.message = format!(
+            "{} ({}x{}x{}): {} blocks",
+            name, schematic.size.x, schematic.size.y, schematic.size.z, placed
+        );
+        for (state, count) in &unsupported {
+            warn!("Skipped {} unsupported {}", count, state);
+            report
+                .message
+                .push_str(&format!("\n  skipped {}x {}", count, state));
+        }
+    }
+}
+
+fn export_schematic(
+    actions: Actions,
+    grid: Res<Grid>,
+    region: Res<ExportRegion>,
+    mut report: ResMut<SchematicReport>,
+) {
+    if !actions.just_pressed(Action::Export) {
+        return;
+    }
+
+    let area = region.area.or_else(|| {
+        let mut positions = grid
+            .iter()
+            .filter(|(_, data)| data.block_type != BlockType::Air)
+            .map(|(position, _)| position);
+        let first = positions.next()?;
+        let (min, max) = positions.fold((first, first), |(min, max), position| {
+            (min.min(position), max.max(position))
+        });
+        Some((min, max - min + IVec3::ONE))
+    });
+    let Some((min, size)) = area else {
+        report.message = "Nothing to export".to_string();
+        return;
+    };
+    if let Err(err) = sponge::check_size(size) {
+        warn!("Could not export: {}", err);
+        report.message = format!("Failed: {}", err);
+        return;
+    }
+
+    let (mut schematic, skipped) = Schematic::from_grid(&grid, min, size);
+    if let Some(data_version) = region.data_version {
+        schematic.data_version = data_version;
+    }
+    schematic.source = region.source.clone();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = Path::new(EXPORT_DIR).join(format!("export-{}.schem", timestamp));
+
+    match write_schematic(&path, &schematic) {
+        Ok(()) => {
+            info!(
+                "Exported {}x{}x{} to {}",
+                size.x,
+                size.y,
+                size.z,
+                path.display()
+            );
+            report.message = format!(
+                "Exported {}x{}x{} to {}",
+                size.x,
+                size.y,
+                size.z,
+                path.display()
+            );
+        }
+        Err(err) => {
+            warn!("Could not export {}: {}", path.display(), err);
+            report.message = format!("Failed: {}", err);
+            return;
+        }
+    }
+
+    for (name, count) in &skipped {
+        warn!(
+            "Exported {} {} without a Minecraft equivalent as air",
+            count, name
+        );
+        report
+            .message
+            .push_str(&format!("\n  {}x {} written as air", count, name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockData, schematic::nbt::COMPOUND};
+
+    /// A 3x2x1 Sponge v3 file, entities are only written when there are any.
+    fn sponge(
+        palette: &[&str],
+        data: Vec<i8>,
+        block_entities: Vec<Tag>,
+        entities: Vec<Tag>,
+    ) -> Tag {
+        let blocks = vec![
+            (
+                "Palette".to_string(),
+                Tag::Compound(
+                    palette
+                        .iter()
+                        .enumerate()
+                        .map(|(id, state)| (state.to_string(), Tag::Int(id as i32)))
+                        .collect(),
+                ),
+            ),
+            ("Data".to_string(), Tag::ByteArray(data)),
+            (
+                "BlockEntities".to_string(),
+                Tag::List(COMPOUND, block_entities),
+            ),
+        ];
+
+        let mut schematic = vec![
+            ("Version".to_string(), Tag::Int(3)),
+            ("DataVersion".to_string(), Tag::Int(3700)),
+            (
+                "Metadata".to_string(),
+                Tag::Compound(vec![("Name".to_string(), Tag::String("lamp".to_string()))]),
+            ),
+            ("Width".to_string(), Tag::Short(3)),
+            ("Height".to_string(), Tag::Short(2)),
+            ("Length".to_string(), Tag::Short(1)),
+            ("Offset".to_string(), Tag::IntArray(vec![4, 0, -2])),
+            ("Blocks".to_string(), Tag::Compound(blocks)),
+        ];
+        if !entities.is_empty() {
+            schematic.push(("Entities".to_string(), Tag::List(COMPOUND, entities)));
+        }
+
+        Tag::Compound(vec![("Schematic".to_string(), Tag::Compound(schematic))])
+    }
+
+    /// Imports the file into an empty grid the way a drop does, then exports the
+    /// region it landed in.
+    fn round_trip(file: &Tag) -> Tag {
+        let bytes = nbt::write_compressed("", file).unwrap();
+        let (_, root) = nbt::read_compressed(&bytes).unwrap();
+        let imported = sponge::parse(&root).unwrap();
+
+        let mut grid = Grid::default();
+        for (position, state) in &imported.blocks {
+            if let MappedState::Block(block_type) = to_block_type(state) {
+                grid.insert(*position, BlockData { block_type });
+            }
+        }
+
+        let (mut exported, _) = Schematic::from_grid(&grid, IVec3::ZERO, imported.size);
+        exported.data_version = imported.data_version;
+        exported.source = imported.source;
+        sponge::write(&exported)
+    }
+
+    #[test]
+    fn exporting_an_import_gives_the_same_nbt() {
+        let file = sponge(
+            &[
+                "minecraft:air",
+                "minecraft:grass_block[snowy=false]",
+                "minecraft:redstone_block",
+                "minecraft:redstone_wire[east=none,north=none,power=0,south=none,west=none]",
+                "minecraft:redstone_lamp[lit=true]",
+            ],
+            vec![1, 1, 1, 2, 3, 4],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        assert_eq!(round_trip(&file), file);
+    }
+
+    #[test]
+    fn exporting_an_import_drops_what_redlab_has_no_place_for() {
+        let chest = Tag::Compound(vec![(
+            "Id".to_string(),
+            Tag::String("minecraft:chest".to_string()),
+        )]);
+        let file = sponge(
+            &[
+                "minecraft:air",
+                "minecraft:grass_block[snowy=true]",
+                "minecraft:chest[facing=north,type=single,waterlogged=false]",
+            ],
+            vec![1, 1, 1, 0, 2, 0],
+            vec![chest.clone()],
+            vec![chest],
+        );
+
+        let exported = round_trip(&file);
+        let schematic = exported.get("Schematic").unwrap();
+        assert!(schematic.get("Entities").is_none());
+        assert_eq!(
+            schematic.get("Blocks").unwrap().get("BlockEntities"),
+            Some(&Tag::List(COMPOUND, Vec::new()))
+        );
+
+        let reimported = sponge::parse(&exported).unwrap();
+        let state_at = |position: IVec3| {
+            reimported
+                .blocks
+                .iter()
+                .find(|(at, _)| *at == position)
+                .map(|(_, state)| state.to_string())
+        };
+        assert_eq!(
+            state_at(IVec3::ZERO).as_deref(),
+            Some("minecraft:grass_block[snowy=false]")
+        );
+        assert_eq!(
+            state_at(IVec3::new(1, 1, 0)).as_deref(),
+            Some("minecraft:air")
+        );
+    }
+}
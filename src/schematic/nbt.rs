@@ -0,0 +1,383 @@
+use std::io::{Read, Write};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+use crate::schematic::SchematicError;
+
+const END: u8 = 0;
+const BYTE: u8 = 1;
+const SHORT: u8 = 2;
+const INT: u8 = 3;
+const LONG: u8 = 4;
+const FLOAT: u8 = 5;
+const DOUBLE: u8 = 6;
+const BYTE_ARRAY: u8 = 7;
+const STRING: u8 = 8;
+const LIST: u8 = 9;
+pub const COMPOUND: u8 = 10;
+const INT_ARRAY: u8 = 11;
+const LONG_ARRAY: u8 = 12;
+
+/// Nesting deeper than this is treated as a malformed file.
+const MAX_DEPTH: usize = 512;
+
+/// A named binary tag. Compounds keep their entries in file order so a file can be
+/// written back exactly as it was read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// Element type id and elements, the id matters for empty lists.
+    List(u8, Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, Tag)] {
+        match self {
+            Tag::Compound(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    pub fn elements(&self) -> &[Tag] {
+        match self {
+            Tag::List(_, elements) => elements,
+            _ => &[],
+        }
+    }
+
+    /// Any integer tag widened to an `i64`.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[i8]> {
+        match self {
+            Tag::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => BYTE,
+            Tag::Short(_) => SHORT,
+            Tag::Int(_) => INT,
+            Tag::Long(_) => LONG,
+            Tag::Float(_) => FLOAT,
+            Tag::Double(_) => DOUBLE,
+            Tag::ByteArray(_) => BYTE_ARRAY,
+            Tag::String(_) => STRING,
+            Tag::List(..) => LIST,
+            Tag::Compound(_) => COMPOUND,
+            Tag::IntArray(_) => INT_ARRAY,
+            Tag::LongArray(_) => LONG_ARRAY,
+        }
+    }
+
+    pub fn as_longs(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(longs) => Some(longs),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a gzip compressed NBT file, uncompressed data is accepted as well. Returns
+/// the name of the root tag and the tag itself.
+pub fn read_compressed(bytes: &[u8]) -> Result<(String, Tag), SchematicError> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut data = Vec::new();
+        GzDecoder::new(bytes)
+            .read_to_end(&mut data)
+            .map_err(|err| SchematicError::Malformed(format!("gzip: {}", err)))?;
+        read(&data)
+    } else {
+        read(bytes)
+    }
+}
+
+pub fn read(data: &[u8]) -> Result<(String, Tag), SchematicError> {
+    let mut reader = Reader { data, offset: 0 };
+    let id = reader.u8()?;
+    if id != COMPOUND {
+        return Err(SchematicError::Malformed(format!(
+            "root tag must be a compound, found {}",
+            id
+        )));
+    }
+    let name = reader.string()?;
+    let tag = reader.payload(id, 0)?;
+    Ok((name, tag))
+}
+
+/// Gzip compressed like Minecraft writes its own NBT files.
+pub fn write_compressed(name: &str, root: &Tag) -> Result<Vec<u8>, SchematicError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&write(name, root))?;
+    Ok(encoder.finish()?)
+}
+
+pub fn write(name: &str, root: &Tag) -> Vec<u8> {
+    let mut data = vec![root.id()];
+    write_string(&mut data, name);
+    write_payload(&mut data, root);
+    data
+}
+
+fn write_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+fn write_payload(data: &mut Vec<u8>, tag: &Tag) {
+    match tag {
+        Tag::Byte(value) => data.push(*value as u8),
+        Tag::Short(value) => data.extend_from_slice(&value.to_be_bytes()),
+        Tag::Int(value) => data.extend_from_slice(&value.to_be_bytes()),
+        Tag::Long(value) => data.extend_from_slice(&value.to_be_bytes()),
+        Tag::Float(value) => data.extend_from_slice(&value.to_be_bytes()),
+        Tag::Double(value) => data.extend_from_slice(&value.to_be_bytes()),
+        Tag::ByteArray(bytes) => {
+            data.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+            data.extend(bytes.iter().map(|b| *b as u8));
+        }
+        Tag::String(value) => write_string(data, value),
+        Tag::List(element_id, elements) => {
+            data.push(*element_id);
+            data.extend_from_slice(&(elements.len() as i32).to_be_bytes());
+            for element in elements {
+                write_payload(data, element);
+            }
+        }
+        Tag::Compound(entries) => {
+            for (name, tag) in entries {
+                data.push(tag.id());
+                write_string(data, name);
+                write_payload(data, tag);
+            }
+            data.push(END);
+        }
+        Tag::IntArray(ints) => {
+            data.extend_from_slice(&(ints.len() as i32).to_be_bytes());
+            for value in ints {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        Tag::LongArray(longs) => {
+            data.extend_from_slice(&(longs.len() as i32).to_be_bytes());
+            for value in longs {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], SchematicError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| SchematicError::Malformed("unexpected end of data".to_string()))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SchematicError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, SchematicError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, SchematicError> {
+        Ok(i16::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, SchematicError> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, SchematicError> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    fn length(&mut self) -> Result<usize, SchematicError> {
+        let len = self.i32()?;
+        usize::try_from(len)
+            .map_err(|_| SchematicError::Malformed(format!("negative length {}", len)))
+    }
+
+    /// Java's modified UTF-8 only differs for nul and supplementary characters, which
+    /// block state names never contain.
+    fn string(&mut self) -> Result<String, SchematicError> {
+        let len = self.i16()? as u16 as usize;
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, id: u8, depth: usize) -> Result<Tag, SchematicError> {
+        if depth > MAX_DEPTH {
+            return Err(SchematicError::Malformed(
+                "tags nested too deep".to_string(),
+            ));
+        }
+
+        let tag = match id {
+            BYTE => Tag::Byte(self.u8()? as i8),
+            SHORT => Tag::Short(self.i16()?),
+            INT => Tag::Int(self.i32()?),
+            LONG => Tag::Long(self.i64()?),
+            FLOAT => Tag::Float(f32::from_be_bytes(self.array()?)),
+            DOUBLE => Tag::Double(f64::from_be_bytes(self.array()?)),
+            BYTE_ARRAY => {
+                let len = self.length()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|b| *b as i8).collect())
+            }
+            STRING => Tag::String(self.string()?),
+            LIST => {
+                let element_id = self.u8()?;
+                let len = self.length()?;
+                let mut elements = Vec::new();
+                for _ in 0..len {
+                    elements.push(self.payload(element_id, depth + 1)?);
+                }
+                Tag::List(element_id, elements)
+            }
+            COMPOUND => {
+                let mut entries = Vec::new();
+                loop {
+                    let id = self.u8()?;
+                    if id == END {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(id, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            INT_ARRAY => {
+                let len = self.length()?;
+                (0..len)
+                    .map(|_| self.i32())
+                    .collect::<Result<_, _>>()
+                    .map(Tag::IntArray)?
+            }
+            LONG_ARRAY => {
+                let len = self.length()?;
+                (0..len)
+                    .map(|_| self.i64())
+                    .collect::<Result<_, _>>()
+                    .map(Tag::LongArray)?
+            }
+            id => return Err(SchematicError::Malformed(format!("unknown tag id {}", id))),
+        };
+        Ok(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Tag {
+        Tag::Compound(vec![
+            ("Name".to_string(), Tag::String("redstone".to_string())),
+            ("Data".to_string(), Tag::ByteArray(vec![1, -2, 3])),
+            (
+                "Nested".to_string(),
+                Tag::List(COMPOUND, vec![Tag::Compound(vec![])]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn reads_back_what_it_wrote() {
+        let data = write("root", &sample());
+        let (name, tag) = read(&data).unwrap();
+        assert_eq!(name, "root");
+        assert_eq!(tag, sample());
+
+        let compressed = write_compressed("root", &sample()).unwrap();
+        assert_eq!(read_compressed(&compressed).unwrap().1, sample());
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let data = write("root", &sample());
+        for len in 0..data.len() {
+            assert!(
+                matches!(read(&data[..len]), Err(SchematicError::Malformed(_))),
+                "read {} of {} bytes",
+                len,
+                data.len()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        let mut data = write("", &Tag::Compound(vec![]));
+        data.pop();
+        data.push(BYTE_ARRAY);
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&(-1i32).to_be_bytes());
+
+        let Err(SchematicError::Malformed(message)) = read(&data) else {
+            panic!("negative length was accepted");
+        };
+        assert!(message.contains("negative length"));
+    }
+
+    #[test]
+    fn rejects_lengths_longer_than_the_data() {
+        let mut data = write("", &Tag::Compound(vec![]));
+        data.pop();
+        data.push(LONG_ARRAY);
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&i32::MAX.to_be_bytes());
+
+        assert!(matches!(read(&data), Err(SchematicError::Malformed(_))));
+    }
+}
@@ -0,0 +1,398 @@
+use bevy::prelude::*;
+
+use crate::schematic::{
+    DATA_VERSION, Schematic, SchematicError,
+    nbt::{COMPOUND, Tag},
+    states::BlockState,
+};
+
+fn required_int(tag: &Tag, name: &str) -> Result<i64, SchematicError> {
+    tag.get(name)
+        .and_then(Tag::as_int)
+        .ok_or_else(|| SchematicError::Malformed(format!("missing {}", name)))
+}
+
+/// Block data is one unsigned LEB128 varint per block.
+fn decode_varints(bytes: &[i8]) -> Result<Vec<usize>, SchematicError> {
+    let mut values = Vec::new();
+    let mut value = 0usize;
+    let mut shift = 0;
+
+    for byte in bytes.iter().map(|b| *b as u8) {
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+            if shift > 28 {
+                return Err(SchematicError::Malformed("varint too long".to_string()));
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+fn encode_varints(ids: &[usize]) -> Vec<i8> {
+    let mut bytes = Vec::new();
+    for id in ids {
+        let mut value = *id;
+        while value >= 0x80 {
+            bytes.push(((value & 0x7f) | 0x80) as u8 as i8);
+            value >>= 7;
+        }
+        bytes.push(value as u8 as i8);
+    }
+    bytes
+}
+
+/// Replaces an entry in place so the order of the file is kept, or appends it.
+fn set(entries: &mut Vec<(String, Tag)>, name: &str, tag: Tag) {
+    match entries.iter_mut().find(|(key, _)| key == name) {
+        Some((_, existing)) => *existing = tag,
+        None => entries.push((name.to_string(), tag)),
+    }
+}
+
+/// Whether a region of `size` can be written. Each axis is stored as a short and read
+/// back unsigned, and the whole volume is indexed with an `i32`.
+pub fn check_size(size: IVec3) -> Result<(), SchematicError> {
+    let volume = size
+        .to_array()
+        .into_iter()
+        .try_fold(1i32, |volume, axis| volume.checked_mul(axis));
+    if size.cmpgt(IVec3::splat(u16::MAX as i32)).any() || volume.is_none() {
+        return Err(SchematicError::Unsupported(format!(
+            "{}x{}x{} is too large for a Sponge schematic",
+            size.x, size.y, size.z
+        )));
+    }
+    Ok(())
+}
+
+/// Sponge schematic versions 2 and 3. Version 3 moved everything under a `Schematic`
+/// compound and the palette and data under `Blocks`.
+pub fn parse(root: &Tag) -> Result<Schematic, SchematicError> {
+    let schematic = root.get("Schematic").unwrap_or(root);
+    let version = schematic.get("Version").and_then(Tag::as_int).unwrap_or(1);
+
+    let width = required_int(schematic, "Width")? as u16 as i32;
+    let height = required_int(schematic, "Height")? as u16 as i32;
+    let length = required_int(schematic, "Length")? as u16 as i32;
+
+    let (palette, data) = if version >= 3 {
+        let blocks = schematic
+            .get("Blocks")
+            .ok_or_else(|| SchematicError::Malformed("missing Blocks".to_string()))?;
+        (blocks.get("Palette"), blocks.get("Data"))
+    } else {
+        (schematic.get("Palette"), schematic.get("BlockData"))
+    };
+
+    let palette =
+        palette.ok_or_else(|| SchematicError::Malformed("missing Palette".to_string()))?;
+    // Ids index into the palette, anything past its end would only make us allocate
+    // whatever the file asks for
+    let count = palette.entries().len();
+    let mut states: Vec<Option<BlockState>> = Vec::new();
+    for (state, id) in palette.entries() {
+        let id = id.as_int().unwrap_or(-1);
+        let Some(id) = usize::try_from(id).ok().filter(|id| *id < count) else {
+            return Err(SchematicError::Malformed(format!(
+                "bad palette id {} for {}",
+                id, state
+            )));
+        };
+        if states.len() <= id {
+            states.resize(id + 1, None);
+        }
+        states[id] = Some(BlockState::parse(state));
+    }
+
+    let data = data
+        .and_then(Tag::as_bytes)
+        .ok_or_else(|| SchematicError::Malformed("missing block data".to_string()))?;
+    let ids = decode_varints(data)?;
+
+    // Each axis is an unsigned short, the volume does not fit in an `i32`
+    let volume = [width, height, length]
+        .into_iter()
+        .try_fold(1usize, |volume, axis| volume.checked_mul(axis as usize))
+        .ok_or_else(|| {
+            SchematicError::Malformed(format!("{}x{}x{} is too large", width, height, length))
+        })?;
+    if ids.len() != volume {
+        return Err(SchematicError::Malformed(format!(
+            "expected {} blocks, found {}",
+            volume,
+            ids.len()
+        )));
+    }
+
+    let mut blocks = Vec::with_capacity(volume);
+    let (columns, rows) = (width as usize, length as usize);
+    for (index, id) in ids.into_iter().enumerate() {
+        let position = IVec3::new(
+            (index % columns) as i32,
+            (index / (columns * rows)) as i32,
+            ((index / columns) % rows) as i32,
+        );
+        let Some(Some(state)) = states.get(id) else {
+            return Err(SchematicError::Malformed(format!(
+                "unknown palette id {}",
+                id
+            )));
+        };
+        blocks.push((position, state.clone()));
+    }
+
+    Ok(Schematic {
+        size: IVec3::new(width, height, length),
+        data_version: schematic
+            .get("DataVersion")
+            .and_then(Tag::as_int)
+            .map_or(DATA_VERSION, |version| version as i32),
+        blocks,
+        source: (version == 3).then(|| schematic.clone()),
+    })
+}
+
+/// Sponge schematic version 3 with ids handed out in the order blocks are first seen,
+/// like WorldEdit does. A schematic that was read from a version 3 file keeps its
+/// metadata and palette order, so writing back an unchanged import gives the same
+/// NBT, except for what RedLab has no place for:
+///
+/// - blocks without a RedLab equivalent are written as air
+/// - block entities and entities are dropped
+/// - grass is always written with `snowy=false`
+/// - dust connections, dust power and whether lamps and torches are lit are written
+///   as the simulation left them, which need not match the imported file
+///
+/// The size must have passed [`check_size`].
+pub fn write(schematic: &Schematic) -> Tag {
+    let size = schematic.size;
+    let mut cells: Vec<Option<&BlockState>> = vec![None; (size.x * size.y * size.z) as usize];
+    for (position, state) in &schematic.blocks {
+        let index = position.x + (position.z + position.y * size.z) * size.x;
+        cells[index as usize] = Some(state);
+    }
+
+    let mut entries = match &schematic.source {
+        Some(Tag::Compound(entries)) => entries.clone(),
+        _ => vec![("Version".to_string(), Tag::Int(3))],
+    };
+
+    let mut palette: Vec<(BlockState, String)> = Vec::new();
+    if let Some(blocks) = schematic
+        .source
+        .as_ref()
+        .and_then(|source| source.get("Blocks"))
+    {
+        let mut known: Vec<(i64, &String)> = blocks
+            .get("Palette")
+            .map(|palette| {
+                palette
+                    .entries()
+                    .iter()
+                    .filter_map(|(name, id)| Some((id.as_int()?, name)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        known.sort();
+        palette.extend(
+            known
+                .into_iter()
+                .map(|(_, name)| (BlockState::parse(name), name.clone())),
+        );
+    }
+
+    let air = BlockState::parse("minecraft:air");
+    let ids: Vec<usize> = cells
+        .into_iter()
+        .map(|state| {
+            let state = state.unwrap_or(&air);
+            match palette.iter().position(|(known, _)| known == state) {
+                Some(id) => id,
+                None => {
+                    palette.push((state.clone(), state.to_string()));
+                    palette.len() - 1
+                }
+            }
+        })
+        .collect();
+
+    let mut blocks = match schematic
+        .source
+        .as_ref()
+        .and_then(|source| source.get("Blocks"))
+    {
+        Some(Tag::Compound(blocks)) => blocks.clone(),
+        _ => Vec::new(),
+    };
+    set(
+        &mut blocks,
+        "Palette",
+        Tag::Compound(
+            palette
+                .into_iter()
+                .enumerate()
+                .map(|(id, (_, name))| (name, Tag::Int(id as i32)))
+                .collect(),
+        ),
+    );
+    set(&mut blocks, "Data", Tag::ByteArray(encode_varints(&ids)));
+    // Block entities and entities belong to blocks RedLab does not have
+    set(
+        &mut blocks,
+        "BlockEntities",
+        Tag::List(COMPOUND, Vec::new()),
+    );
+    entries.retain(|(name, _)| name != "Entities");
+
+    set(
+        &mut entries,
+        "DataVersion",
+        Tag::Int(schematic.data_version),
+    );
+    // Axes past `i16::MAX` wrap negative and read back unsigned
+    set(&mut entries, "Width", Tag::Short(size.x as i16));
+    set(&mut entries, "Height", Tag::Short(size.y as i16));
+    set(&mut entries, "Length", Tag::Short(size.z as i16));
+    if !entries.iter().any(|(name, _)| name == "Offset") {
+        entries.push(("Offset".to_string(), Tag::IntArray(vec![0, 0, 0])));
+    }
+    set(&mut entries, "Blocks", Tag::Compound(blocks));
+
+    Tag::Compound(vec![("Schematic".to_string(), Tag::Compound(entries))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schematic(width: Tag, height: Tag, length: Tag, data: Vec<i8>) -> Tag {
+        let palette = vec![
+            ("minecraft:air".to_string(), Tag::Int(0)),
+            ("minecraft:redstone_block".to_string(), Tag::Int(1)),
+        ];
+        with_palette(palette, width, height, length, data)
+    }
+
+    fn with_palette(
+        palette: Vec<(String, Tag)>,
+        width: Tag,
+        height: Tag,
+        length: Tag,
+        data: Vec<i8>,
+    ) -> Tag {
+        Tag::Compound(vec![(
+            "Schematic".to_string(),
+            Tag::Compound(vec![
+                ("Version".to_string(), Tag::Int(3)),
+                ("Width".to_string(), width),
+                ("Height".to_string(), height),
+                ("Length".to_string(), length),
+                (
+                    "Blocks".to_string(),
+                    Tag::Compound(vec![
+                        ("Palette".to_string(), Tag::Compound(palette)),
+                        ("Data".to_string(), Tag::ByteArray(data)),
+                    ]),
+                ),
+            ]),
+        )])
+    }
+
+    #[test]
+    fn places_blocks_x_first_then_z_then_y() {
+        let root = schematic(
+            Tag::Short(2),
+            Tag::Short(2),
+            Tag::Short(1),
+            vec![0, 1, 1, 0],
+        );
+        let parsed = parse(&root).unwrap();
+
+        assert_eq!(parsed.size, IVec3::new(2, 2, 1));
+        let redstone: Vec<IVec3> = parsed
+            .blocks
+            .iter()
+            .filter(|(_, state)| state.name == "minecraft:redstone_block")
+            .map(|(position, _)| *position)
+            .collect();
+        assert_eq!(redstone, [IVec3::new(1, 0, 0), IVec3::new(0, 1, 0)]);
+    }
+
+    #[test]
+    fn rejects_truncated_block_data() {
+        let root = schematic(Tag::Short(2), Tag::Short(2), Tag::Short(1), vec![0, 1, 1]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+
+        // A varint cut off halfway
+        let root = schematic(Tag::Short(1), Tag::Short(1), Tag::Short(1), vec![-128]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+
+    #[test]
+    fn reads_negative_lengths_as_unsigned() {
+        let root = schematic(Tag::Short(1), Tag::Short(1), Tag::Short(-1), vec![0]);
+        let Err(SchematicError::Malformed(message)) = parse(&root) else {
+            panic!("negative length was accepted");
+        };
+        assert!(message.contains("expected 65535 blocks"));
+    }
+
+    #[test]
+    fn rejects_oversized_volumes() {
+        let root = schematic(Tag::Int(65535), Tag::Int(65535), Tag::Int(65535), vec![0]);
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+
+    #[test]
+    fn rejects_palette_ids_past_the_palette() {
+        let palette = vec![
+            ("minecraft:air".to_string(), Tag::Int(0)),
+            ("minecraft:redstone_block".to_string(), Tag::Int(i32::MAX)),
+        ];
+        let root = with_palette(
+            palette,
+            Tag::Short(1),
+            Tag::Short(1),
+            Tag::Short(1),
+            vec![0],
+        );
+        let Err(SchematicError::Malformed(message)) = parse(&root) else {
+            panic!("out of range palette id was accepted");
+        };
+        assert!(message.contains("bad palette id"));
+
+        let palette = vec![
+            ("minecraft:air".to_string(), Tag::Int(0)),
+            ("minecraft:redstone_block".to_string(), Tag::Int(2)),
+        ];
+        let root = with_palette(
+            palette,
+            Tag::Short(1),
+            Tag::Short(1),
+            Tag::Short(1),
+            vec![0],
+        );
+        assert!(matches!(parse(&root), Err(SchematicError::Malformed(_))));
+    }
+
+    #[test]
+    fn only_regions_the_format_can_hold_are_written() {
+        assert!(check_size(IVec3::new(65535, 1, 1)).is_ok());
+        assert!(check_size(IVec3::new(2048, 256, 2048)).is_ok());
+        assert!(matches!(
+            check_size(IVec3::new(65536, 1, 1)),
+            Err(SchematicError::Unsupported(_))
+        ));
+        assert!(matches!(
+            check_size(IVec3::new(4096, 4096, 4096)),
+            Err(SchematicError::Unsupported(_))
+        ));
+    }
+}
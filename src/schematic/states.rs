@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use std::fmt;
+
+use crate::blocks::{
+    BlockType, Dirt, Dropper, Dust, RedStone, RedStoneLamp, RedStoneTorch, StandardGrass, Tickable,
+};
+
+/// A Minecraft block state such as `minecraft:redstone_wire[east=side,power=5]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockState {
+    pub name: String,
+    pub properties: Vec<(String, String)>,
+}
+
+impl BlockState {
+    pub fn parse(state: &str) -> Self {
+        let (name, properties) = match state.split_once('[') {
+            Some((name, rest)) => (name, rest.trim_end_matches(']')),
+            None => (state, ""),
+        };
+
+        let properties = properties
+            .split(',')
+            .filter_map(|property| property.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        Self {
+            name: qualified(name.trim()),
+            properties,
+        }
+    }
+
+    fn new(name: &str, properties: &[(&str, String)]) -> Self {
+        Self {
+            name: qualified(name),
+            properties: properties
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.properties.is_empty() {
+            return Ok(());
+        }
+
+        let properties: Vec<String> = self
+            .properties
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        write!(f, "[{}]", properties.join(","))
+    }
+}
+
+fn qualified(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+pub enum MappedState {
+    Air,
+    Block(BlockType),
+    Unsupported,
+}
+
+/// Minecraft's horizontal facing names, north is towards negative Z.
+pub fn facing_to_dir(facing: &str) -> Option<IVec3> {
+    match facing {
+        "north" => Some(IVec3::NEG_Z),
+        "south" => Some(IVec3::Z),
+        "east" => Some(IVec3::X),
+        "west" => Some(IVec3::NEG_X),
+        _ => None,
+    }
+}
+
+/// Facing names of blocks that can also point up and down, like droppers.
+fn facing_to_dir_3d(facing: &str) -> Option<IVec3> {
+    match facing {
+        "up" => Some(IVec3::Y),
+        "down" => Some(IVec3::NEG_Y),
+        facing => facing_to_dir(facing),
+    }
+}
+
+fn dir_to_facing_3d(dir: IVec3) -> Option<&'static str> {
+    match dir {
+        IVec3::Y => Some("up"),
+        IVec3::NEG_Y => Some("down"),
+        dir => dir_to_facing(dir),
+    }
+}
+
+pub fn dir_to_facing(dir: IVec3) -> Option<&'static str> {
+    match dir {
+        IVec3::NEG_Z => Some("north"),
+        IVec3::Z => Some("south"),
+        IVec3::X => Some("east"),
+        IVec3::NEG_X => Some("west"),
+        _ => None,
+    }
+}
+
+/// The Minecraft block state for a block, with properties in alphabetical order like
+/// the game writes them. `None` for blocks Minecraft has no equivalent for, like a
+/// torch hanging from a ceiling.
+pub fn from_block_type(block_type: &BlockType) -> Option<BlockState> {
+    let state = match block_type {
+        BlockType::Air => BlockState::new("air", &[]),
+        BlockType::StandardGrass(_) => {
+            BlockState::new("grass_block", &[("snowy", "false".to_string())])
+        }
+        BlockType::Dirt(_) => BlockState::new("dirt", &[]),
+        BlockType::RedStone(_) => BlockState::new("redstone_block", &[]),
+        BlockType::RedStoneLamp(lamp) => {
+            BlockState::new("redstone_lamp", &[("lit", (lamp.power() > 0).to_string())])
+        }
+        BlockType::RedStoneTorch(torch) if torch.attached_face == IVec3::Y => {
+            BlockState::new("redstone_torch", &[("lit", torch.lit.to_string())])
+        }
+        BlockType::RedStoneTorch(torch) => BlockState::new(
+            "redstone_wall_torch",
+            &[
+                ("facing", dir_to_facing(torch.attached_face)?.to_string()),
+                ("lit", torch.lit.to_string()),
+            ],
+        ),
+        BlockType::Dust(dust) => {
+            let [north, south, east, west] = dust
+                .shape
+                .connections()
+                .map(|side| if side { "side" } else { "none" }.to_string());
+            BlockState::new(
+                "redstone_wire",
+                &[
+                    ("east", east),
+                    ("north", north),
+                    ("power", dust.power().to_string()),
+                    ("south", south),
+                    ("west", west),
+                ],
+            )
+        }
+        BlockType::Dropper(dropper) => BlockState::new(
+            "dropper",
+            &[
+                ("facing", dir_to_facing_3d(dropper.facing)?.to_string()),
+                ("triggered", dropper.triggered.to_string()),
+            ],
+        ),
+    };
+    Some(state)
+}
+
+/// Power and dust shapes are taken as a starting point only, they are recomputed
+/// once the block is placed.
+pub fn to_block_type(state: &BlockState) -> MappedState {
+    let lit = state.property("lit") == Some("true");
+
+    let block_type = match state.name.trim_start_matches("minecraft:") {
+        "air" | "cave_air" | "void_air" => return MappedState::Air,
+        "grass_block" => BlockType::StandardGrass(StandardGrass),
+        "dirt" => BlockType::Dirt(Dirt),
+        "redstone_block" => BlockType::RedStone(RedStone),
+        "redstone_lamp" => {
+            BlockType::RedStoneLamp(RedStoneLamp::default().with_power(if lit { 15 } else { 0 }))
+        }
+        "redstone_wire" => {
+            let power = state
+                .property("power")
+                .and_then(|power| power.parse().ok())
+                .unwrap_or(0);
+            BlockType::Dust(Dust::default().with_power(power))
+        }
+        "redstone_torch" => BlockType::RedStoneTorch(RedStoneTorch {
+            lit,
+            attached_face: IVec3::Y,
+        }),
+        "redstone_wall_torch" => {
+            // Wall torches face away from the block they hang on
+            let Some(facing) = state.property("facing").and_then(facing_to_dir) else {
+                return MappedState::Unsupported;
+            };
+            BlockType::RedStoneTorch(RedStoneTorch {
+                lit,
+                attached_face: facing,
+            })
+        }
+        "dropper" => {
+            let Some(facing) = state.property("facing").and_then(facing_to_dir_3d) else {
+                return MappedState::Unsupported;
+            };
+            BlockType::Dropper(Dropper {
+                facing,
+                triggered: state.property("triggered") == Some("true"),
+            })
+        }
+        _ => return MappedState::Unsupported,
+    };
+
+    MappedState::Block(block_type)
+}
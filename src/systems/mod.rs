@@ -2,7 +2,10 @@ use bevy::prelude::*;
 
 use crate::{
     blocks::{Block, RecomputedResult},
-    grid_plugin::{BlockChange, BlockChangeQueue, Grid, Place, Remove},
+    grid_plugin::{
+        BlockChange, BlockChangeQueue, Grid, Place, Remove, TracedUpdate, UpdateSource, UpdateTrace,
+    },
+    redstone::GlobalTick,
     render::{DirtyBlocks, DirtyRender},
 };
 
@@ -11,7 +14,10 @@ pub fn recalculate_dirty_blocks(
     grid: Res<Grid>,
     mut render_dirty: ResMut<DirtyRender>,
     mut queue: ResMut<BlockChangeQueue>,
+    mut trace: ResMut<UpdateTrace>,
+    tick_counter: Res<GlobalTick>,
 ) {
+    let now = tick_counter.read();
     for position in dirty_blocks.drain() {
         let Some(block_data) = grid.get(position) else {
             continue;
@@ -20,6 +26,10 @@ pub fn recalculate_dirty_blocks(
         let result = block_data.block_type.neighbor_changed(&grid, position);
 
         info!(?position, ?result);
+        trace.record(
+            now,
+            TracedUpdate::new(position, UpdateSource::Neighbour, &result),
+        );
 
         match result {
             RecomputedResult::Changed {
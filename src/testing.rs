@@ -0,0 +1,131 @@
+//! A headless world running the simulation the way `FixedUpdate` does, one game tick
+//! per [`TestWorld::tick`].
+
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use bevy_prng::WyRand;
+use bevy_rand::prelude::GlobalRng;
+use rand_core::SeedableRng;
+
+use crate::{
+    blocks::{BlockType, NeighbourUpdate},
+    grid_plugin::{
+        BlockChange, BlockChangeQueue, Grid, Remove, UpdateLoopGuard, UpdateTrace,
+        grid_apply_changes,
+    },
+    interactions::placement,
+    redstone::{
+        GlobalTick, Scheduler, Tick,
+        graph::{CompiledBackend, run_compiled_backend},
+        islands::CircuitIslands,
+        rules::SimulationRules,
+        ticks::{GlobalTickEvent, tick_the_counter},
+    },
+    render::{DirtyBlocks, DirtyRender},
+    systems::recalculate_dirty_blocks,
+};
+
+pub(crate) struct TestWorld {
+    world: World,
+    schedule: Schedule,
+}
+
+impl TestWorld {
+    pub fn new() -> Self {
+        ComputeTaskPool::get_or_init(TaskPool::default);
+
+        let mut world = World::new();
+        world.init_resource::<Grid>();
+        world.init_resource::<BlockChangeQueue>();
+        world.init_resource::<Scheduler>();
+        world.init_resource::<GlobalTick>();
+        world.init_resource::<DirtyBlocks>();
+        world.init_resource::<DirtyRender>();
+        world.init_resource::<UpdateLoopGuard>();
+        world.init_resource::<UpdateTrace>();
+        world.init_resource::<CircuitIslands>();
+        world.init_resource::<CompiledBackend>();
+        world.init_resource::<Messages<GlobalTickEvent>>();
+        world.spawn((WyRand::seed_from_u64(0), GlobalRng));
+        world.resource_mut::<GlobalTick>().start();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                tick_the_counter,
+                run_compiled_backend,
+                grid_apply_changes,
+                recalculate_dirty_blocks,
+            )
+                .chain(),
+        );
+
+        Self { world, schedule }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        self.world.resource::<Grid>()
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        self.world.resource::<Scheduler>()
+    }
+
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler {
+        self.world.resource_mut::<Scheduler>().into_inner()
+    }
+
+    pub fn loop_guard(&self) -> &UpdateLoopGuard {
+        self.world.resource::<UpdateLoopGuard>()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.world.resource::<GlobalTick>().is_running()
+    }
+
+    pub fn compiled_backend(&self) -> &CompiledBackend {
+        self.world.resource::<CompiledBackend>()
+    }
+
+    pub fn toggle_compiled_backend(&mut self) {
+        self.world.resource_mut::<CompiledBackend>().toggle();
+    }
+
+    pub fn rules_mut(&mut self) -> &mut SimulationRules {
+        self.world.resource_mut::<Grid>().into_inner().rules_mut()
+    }
+
+    pub fn now(&self) -> Tick {
+        self.world.resource::<GlobalTick>().read()
+    }
+
+    /// Places a block the way a click on the face pointing along `facing` does, it
+    /// lands with the next tick.
+    pub fn place(&mut self, block_type: BlockType, position: IVec3, facing: IVec3) {
+        let change =
+            placement(self.grid(), block_type, position, facing).expect("block can be placed");
+        self.world
+            .resource_mut::<BlockChangeQueue>()
+            .push_input(change);
+    }
+
+    /// Breaks the block at `position` the way a player does, with the next tick.
+    pub fn remove(&mut self, position: IVec3) {
+        let change = Remove::new(position, true, None, NeighbourUpdate::EXTENDED.to_vec());
+        self.world
+            .resource_mut::<BlockChangeQueue>()
+            .push_input(BlockChange::Remove(change));
+    }
+
+    pub fn tick(&mut self) {
+        self.schedule.run(&mut self.world);
+    }
+
+    pub fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+}
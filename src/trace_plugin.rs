@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use std::{
+    fmt, fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    GameLoop,
+    bindings::{Action, Actions},
+    grid_plugin::{TracedUpdate, UpdateTrace},
+    redstone::Tick,
+    render::{trace_info, trace_labels},
+};
+
+/// Lines of the trace shown at once.
+pub const TRACE_LINES: usize = 24;
+
+const TRACE_DIR: &str = "traces";
+
+pub struct TracePlugin;
+
+impl Plugin for TracePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TracePanel>()
+            .add_systems(Update, use_trace_panel.in_set(GameLoop::Input))
+            .add_systems(Update, (trace_info, trace_labels).in_set(GameLoop::Render));
+    }
+}
+
+/// The panel listing the update trace. `scroll` counts lines up from the newest one,
+/// so the panel follows new ticks until it is scrolled back.
+#[derive(Resource, Default)]
+pub struct TracePanel {
+    open: bool,
+    scroll: usize,
+    message: String,
+}
+
+impl TracePanel {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Outcome of the latest export.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+pub enum TraceLine<'a> {
+    Tick(Tick),
+    Update(usize, &'a TracedUpdate),
+}
+
+impl fmt::Display for TraceLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceLine::Tick(tick) => write!(f, "Tick {}", tick),
+            TraceLine::Update(index, update) => write!(f, "{:>4} {}", index, update),
+        }
+    }
+}
+
+/// A header per tick followed by its updates, numbered in the order they ran. Lines
+/// are only formatted when displayed, a trace can hold thousands of them.
+pub fn trace_lines(trace: &UpdateTrace) -> impl Iterator<Item = TraceLine<'_>> {
+    trace.ticks().flat_map(|tick| {
+        std::iter::once(TraceLine::Tick(tick.tick)).chain(
+            tick.updates
+                .iter()
+                .enumerate()
+                .map(|(index, update)| TraceLine::Update(index, update)),
+        )
+    })
+}
+
+fn write_trace(path: &Path, trace: &UpdateTrace) -> io::Result<()> {
+    let mut text = String::new();
+    for line in trace_lines(trace) {
+        text.push_str(&format!("{}\n", line));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+}
+
+fn use_trace_panel(actions: Actions, trace: Res<UpdateTrace>, mut panel: ResMut<TracePanel>) {
+    if actions.just_pressed(Action::ToggleTrace) {
+        panel.open = !panel.open;
+        panel.scroll = 0;
+    }
+
+    let page = TRACE_LINES / 2;
+    if actions.just_pressed(Action::ScrollTraceUp) {
+        let lines = trace_lines(&trace).count();
+        panel.scroll = (panel.scroll + page).min(lines.saturating_sub(TRACE_LINES));
+    }
+    if actions.just_pressed(Action::ScrollTraceDown) {
+        panel.scroll = panel.scroll.saturating_sub(page);
+    }
+
+    if !actions.just_pressed(Action::ExportTrace) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = Path::new(TRACE_DIR).join(format!("trace-{}.log", timestamp));
+
+    let lines = trace_lines(&trace).count();
+    panel.message = match write_trace(&path, &trace) {
+        Ok(()) => {
+            info!("Exported {} trace lines to {}", lines, path.display());
+            format!("Exported {} lines to {}", lines, path.display())
+        }
+        Err(err) => {
+            warn!("Trace export failed: {}", err);
+            format!("Export failed: {}", err)
+        }
+    };
+}
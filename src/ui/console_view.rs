@@ -0,0 +1,31 @@
+use bevy::{color::palettes::css::GHOST_WHITE, prelude::*};
+
+use crate::ui::{ConsolePanel, ConsoleText};
+
+pub fn console_view_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let fonts: Handle<Font> = asset_server.load("fonts/retro_gaming.ttf");
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(30),
+            left: px(15),
+            width: percent(60),
+            padding: UiRect::all(px(6)),
+            display: Display::None,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Pickable::IGNORE,
+        ConsolePanel,
+        children![(
+            Text::default(),
+            TextFont {
+                font: fonts.clone(),
+                font_size: 17.0,
+                ..default()
+            },
+            TextColor(GHOST_WHITE.into()),
+            ConsoleText,
+        )],
+    ));
+}
@@ -1,6 +1,12 @@
-use bevy::{color::palettes::css::GHOST_WHITE, prelude::*};
+use bevy::{
+    color::palettes::css::{GHOST_WHITE, GRAY, RED},
+    prelude::*,
+};
 
-use crate::ui::{BlockPosInfo, BlockPowerInfo, Immediate, Scheduled, TickText};
+use crate::ui::{
+    BackendText, BlockPosInfo, BlockPowerInfo, EditionText, Immediate, InspectorText, LoopInfo,
+    SaveList, Scheduled, SchematicInfo, SelectionText, TickText, TimelineFill, TimelineText,
+};
 
 pub fn debug_view_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     let fonts: Handle<Font> = asset_server.load("fonts/retro_gaming.ttf");
@@ -39,6 +45,69 @@ pub fn debug_view_system(mut commands: Commands, asset_server: Res<AssetServer>)
                 children![(TextSpan::default(), TickText)],
             ));
 
+            builder.spawn((
+                Text::new("Edition: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                children![(TextSpan::default(), EditionText)],
+            ));
+
+            builder.spawn((
+                Text::new("Backend: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                children![(TextSpan::default(), BackendText)],
+            ));
+
+            builder.spawn((
+                Text::new("Timeline: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                children![(TextSpan::default(), TimelineText)],
+            ));
+
+            builder.spawn((
+                Node {
+                    width: px(200),
+                    height: px(6),
+                    margin: UiRect::vertical(px(4)),
+                    ..default()
+                },
+                BackgroundColor(GRAY.into()),
+                children![(
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(RED.into()),
+                    TimelineFill,
+                )],
+            ));
+
+            builder.spawn((
+                Text::new("Selection: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                children![(TextSpan::default(), SelectionText)],
+            ));
+
             builder.spawn((
                 Text::new("BlockInfo: "),
                 TextFont {
@@ -71,6 +140,27 @@ pub fn debug_view_system(mut commands: Commands, asset_server: Res<AssetServer>)
                 children![(TextSpan::new(""), BlockPowerInfo)],
             ));
 
+            builder.spawn((
+                Text::new("Inspector: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+            ));
+
+            builder.spawn((
+                Text::default(),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                InspectorText,
+            ));
+
             builder.spawn((
                 Text::new("Scheduler: "),
                 TextFont {
@@ -102,8 +192,77 @@ pub fn debug_view_system(mut commands: Commands, asset_server: Res<AssetServer>)
                 TextColor(GHOST_WHITE.into()),
                 Scheduled,
             ));
+
+            builder.spawn((
+                Text::default(),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(RED.into()),
+                LoopInfo,
+            ));
+        })
+        .id();
+
+    let right_column = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::End,
+                margin: UiRect::axes(px(15), px(5)),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text::new("Saves: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+            ));
+
+            builder.spawn((
+                Text::default(),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                SaveList,
+            ));
+
+            builder.spawn((
+                Text::new("Schematic: "),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+            ));
+
+            builder.spawn((
+                Text::default(),
+                TextFont {
+                    font: fonts.clone(),
+                    font_size: 17.0,
+                    ..default()
+                },
+                TextColor(GHOST_WHITE.into()),
+                SchematicInfo,
+            ));
         })
         .id();
 
-    commands.entity(root_uinode).add_child(left_column);
+    commands
+        .entity(root_uinode)
+        .add_children(&[left_column, right_column]);
 }
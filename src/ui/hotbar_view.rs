@@ -0,0 +1,123 @@
+use bevy::{
+    camera::{ClearColorConfig, RenderTarget, ScalingMode, visibility::RenderLayers},
+    prelude::*,
+    render::render_resource::{
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    },
+};
+
+use crate::{interactions::SLOTS, ui::HotbarSlot};
+
+/// Pixels per slot, both in the icon image and on screen.
+const SLOT_SIZE: u32 = 48;
+
+/// World units between two icons.
+const ICON_SPACING: f32 = 2.0;
+
+/// Icons are only seen by the icon camera.
+pub const ICON_LAYER: usize = 1;
+
+fn icon_camera() -> Transform {
+    Transform::from_xyz(10.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y)
+}
+
+/// Where the icon of a slot sits, side by side as seen by the icon camera.
+pub fn icon_position(slot: usize) -> Vec3 {
+    let offset = slot as f32 - (SLOTS - 1) as f32 / 2.0;
+    icon_camera().right() * offset * ICON_SPACING
+}
+
+/// Renders the hotbar icons into an image and shows it with a frame per slot.
+pub fn hotbar_view_system(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d {
+        width: SLOT_SIZE * SLOTS as u32,
+        height: SLOT_SIZE,
+        ..default()
+    };
+
+    let mut icons = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    icons.resize(size);
+    let icons = images.add(icons);
+
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: -1,
+            target: RenderTarget::Image(icons.clone().into()),
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        Projection::from(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed {
+                width: SLOTS as f32 * ICON_SPACING,
+                height: ICON_SPACING,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        Msaa::Off,
+        icon_camera(),
+        RenderLayers::layer(ICON_LAYER),
+    ));
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 1000.,
+            ..default()
+        },
+        icon_camera(),
+        RenderLayers::layer(ICON_LAYER),
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: px(30),
+                width: percent(100),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        width: px((SLOT_SIZE * SLOTS as u32) as f32),
+                        height: px(SLOT_SIZE as f32),
+                        ..default()
+                    },
+                    ImageNode::new(icons),
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+                    Pickable::IGNORE,
+                ))
+                .with_children(|builder| {
+                    for slot in 0..SLOTS {
+                        builder.spawn((
+                            Node {
+                                width: px(SLOT_SIZE as f32),
+                                height: px(SLOT_SIZE as f32),
+                                border: UiRect::all(px(2)),
+                                ..default()
+                            },
+                            BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.3)),
+                            Pickable::IGNORE,
+                            HotbarSlot(slot),
+                        ));
+                    }
+                });
+        });
+}
@@ -1,20 +1,81 @@
 use bevy::prelude::*;
 
+mod console_view;
 mod debug_view;
+mod hotbar_view;
+mod trace_view;
 
+pub use console_view::console_view_system;
 pub use debug_view::debug_view_system;
+pub use hotbar_view::{ICON_LAYER, hotbar_view_system, icon_position};
+pub use trace_view::trace_view_system;
 
 #[derive(Component)]
 pub struct TickText;
 
+#[derive(Component)]
+pub struct EditionText;
+
+#[derive(Component)]
+pub struct BackendText;
+
+#[derive(Component)]
+pub struct TimelineText;
+
+#[derive(Component)]
+pub struct TimelineFill;
+
+#[derive(Component)]
+pub struct SelectionText;
+
 #[derive(Component)]
 pub struct BlockPosInfo;
 
 #[derive(Component)]
 pub struct BlockPowerInfo;
 
+#[derive(Component)]
+pub struct InspectorText;
+
 #[derive(Component)]
 pub struct Immediate;
 
 #[derive(Component)]
 pub struct Scheduled;
+
+#[derive(Component)]
+pub struct LoopInfo;
+
+#[derive(Component)]
+pub struct SaveList;
+
+#[derive(Component)]
+pub struct SchematicInfo;
+
+#[derive(Component)]
+pub struct ConsolePanel;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+#[derive(Component)]
+pub struct HotbarIcon;
+
+/// UI text kept over a point in the world.
+#[derive(Component)]
+pub struct WorldLabel(pub Vec3);
+
+#[derive(Component)]
+pub struct PowerLabel;
+
+#[derive(Component)]
+pub struct TraceLabel;
+
+#[derive(Component)]
+pub struct TraceView;
+
+#[derive(Component)]
+pub struct TraceText;